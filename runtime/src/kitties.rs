@@ -1,60 +1,561 @@
 use support::{
 	decl_module, decl_storage, decl_event, ensure, StorageValue, StorageMap,
-	Parameter, traits::Currency
+	Parameter, traits::{Currency, ReservableCurrency, Get, Imbalance, WithdrawReason, ExistenceRequirement, OnUnbalanced}
 };
-use runtime_primitives::traits::{SimpleArithmetic, Bounded, One, Member};
+use runtime_primitives::Perbill;
+use runtime_primitives::traits::{SimpleArithmetic, Bounded, One, Member, Zero, As, CheckedAdd, CheckedSub, CheckedMul, Saturating};
 use parity_codec::{Encode, Decode};
 use runtime_io::blake2_128;
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
+#[cfg(feature = "std")]
+use runtime_io::with_storage;
+use rstd::prelude::*;
 use rstd::result;
 use crate::linked_item::{LinkedList, LinkedItem};
 
+/// Handler for kitty ownership changes
+pub trait OnKittyTransfer<AccountId, KittyIndex> {
+	/// Called after a kitty is transferred
+	fn on_transfer(from: &AccountId, to: &AccountId, kitty_id: KittyIndex);
+}
+
+impl<AccountId, KittyIndex> OnKittyTransfer<AccountId, KittyIndex> for () {
+	fn on_transfer(_: &AccountId, _: &AccountId, _: KittyIndex) {}
+}
+
+/// Gene mixing of bred kitties
+pub trait BreedingStrategy {
+	/// DNA of a child of parents with the given DNA, before random mutations.
+	/// One byte of the random selector is supplied for each byte of the child DNA.
+	/// Selector bits are biased toward the rarer parent, see `weight_selector`.
+	/// DNA of another length than the selector is truncated or padded with zeros.
+	fn combine(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8>;
+}
+
+/// Picks each bit from either parent, see `combine_parents_dna`
+impl BreedingStrategy for () {
+	fn combine(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8> {
+		combine_parents_dna(dna1, dna2, selector)
+	}
+}
+
 pub trait Trait: system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 	type KittyIndex: Parameter + Member + SimpleArithmetic + Bounded + Default + Copy;
-	type Currency: Currency<Self::AccountId>;
+	/// Currency of fees and deposits
+	type Currency: ReservableCurrency<Self::AccountId>;
+	/// Currency kitties are priced in and bought with. Can be the same as `Currency`.
+	type MarketCurrency: ReservableCurrency<Self::AccountId>;
+	/// Max number of kitties can be transferred in a single batch
+	type MaxBatchSize: Get<u32>;
+	/// Max number of kitties can be created in a single batch
+	type MaxCreateBatchSize: Get<u32>;
+	/// Chance out of 256 of each DNA byte to mutate on breeding
+	type MutationRate: Get<u8>;
+	/// Fee to create a new kitty. The fee is handled by `OnFeePaid`.
+	type CreationFee: Get<BalanceOf<Self>>;
+	/// Fee to breed kitties at low supply. The fee is handled by `OnFeePaid`.
+	type BreedFeeBase: Get<BalanceOf<Self>>;
+	/// Number of kitties in existence for each increase of the breeding fee by `BreedFeeBase`.
+	/// Zero for a constant breeding fee.
+	type BreedFeeStep: Get<u32>;
+	/// Handler of creation and breeding fees, e.g. a treasury. `()` burns the fees.
+	type OnFeePaid: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	/// Percentage of the sale price paid to the original breeder on each sale
+	type RoyaltyPercent: Get<u32>;
+	/// Deposit reserved from the owner while a kitty is listed for sale
+	type ListingDeposit: Get<BalanceOf<Self>>;
+	/// Minimum price of a kitty listed for sale. Should be at least the existential deposit.
+	type MinListingPrice: Get<MarketBalanceOf<Self>>;
+	/// Number of bytes of kitty DNA
+	type DnaLength: Get<u32>;
+	/// Max number of kitties can ever be created
+	type MaxKitties: Get<u32>;
+	/// Max number of English auctions settled in a single block
+	type MaxAuctionsSettledPerBlock: Get<u32>;
+	/// Percentage of the sale price paid to the fee collector on each sale
+	type MarketplaceFeePercent: Get<u32>;
+	/// Account receiving the marketplace fees
+	type FeeCollector: Get<Self::AccountId>;
+	/// Number of times a kitty can be a parent
+	type BreedingCharges: Get<u32>;
+	/// Chance out of 256 of breeding to produce twins
+	type TwinChance: Get<u8>;
+	/// Blocks a generation 0 kitty must wait between breedings. Doubled for each generation.
+	type BaseCooldown: Get<Self::BlockNumber>;
+	/// Fee to reset the breeding cooldown of a kitty. The fee is handled by `OnFeePaid`.
+	type CooldownResetFee: Get<BalanceOf<Self>>;
+	/// Maximum generation of a bred kitty
+	type MaxGeneration: Get<u32>;
+	/// Kitties sharing an ancestor within this many generations cannot breed. Zero to allow inbreeding.
+	type InbreedingDepth: Get<u32>;
+	/// Whether ids of destroyed kitties are reused by new kitties
+	type RecycleIds: Get<bool>;
+	/// Blocks an account must wait between claims of a free kitty from the faucet
+	type FaucetCooldown: Get<Self::BlockNumber>;
+	/// Account owning abandoned kitties until they are adopted
+	type NullAccount: Get<Self::AccountId>;
+	/// Gene mixing of bred kitties. `()` picks each bit from either parent.
+	type BreedingStrategy: BreedingStrategy;
+	/// Handler called when a kitty changes owner
+	type OnKittyTransfer: OnKittyTransfer<Self::AccountId, Self::KittyIndex>;
 }
 
+/// Max length of the seed supplied to `create_with_seed`
+const MAX_SEED_LENGTH: usize = 32;
+/// Max length of the metadata URI of a kitty
+const MAX_METADATA_LENGTH: usize = 256;
+/// Max length of the name of a kitty
+const MAX_NAME_LENGTH: usize = 32;
+/// Max length of the memo of `transfer_with_memo`
+const MAX_MEMO_LENGTH: usize = 64;
+/// Max number of expired listings removed in a single block
+const MAX_EXPIRED_LISTINGS_PER_BLOCK: usize = 50;
+/// Max number of kitties migrated in a single block
+const MAX_MIGRATED_KITTIES_PER_BLOCK: u64 = 50;
+/// Version of the storage layout. Bump it and add a migration when the layout changes.
+/// 0: `Kitty([u8; 16])`
+/// 1: `Kitty { dna, generation, birth }`
+const STORAGE_VERSION: u32 = 1;
+
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+type MarketBalanceOf<T> = <<T as Trait>::MarketCurrency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type MarketNegativeImbalanceOf<T> = <<T as Trait>::MarketCurrency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Note: Changing the layout of this struct requires a migration of the existing `Kitties` storage.
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+pub struct Kitty<BlockNumber> {
+	/// `Trait::DnaLength` bytes
+	pub dna: Vec<u8>,
+	/// 0 for created kitties, one more than the highest parent generation for bred kitties
+	pub generation: u32,
+	/// The block number at which the kitty is created
+	pub birth: BlockNumber,
+}
+
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+#[derive(Encode, Decode, Clone, Copy)]
+pub enum KittyGender {
+	Male,
+	Female,
+}
+
+/// Full details of a kitty, returned by the runtime API
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+#[derive(Encode, Decode)]
+pub struct KittyDetails<AccountId, Balance> {
+	pub dna: Vec<u8>,
+	pub owner: AccountId,
+	pub price: Option<Balance>,
+	pub generation: u32,
+	pub gender: KittyGender,
+}
+
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+#[derive(Encode, Decode)]
+pub struct DutchAuction<Balance, BlockNumber> {
+	pub start_price: Balance,
+	pub end_price: Balance,
+	pub start_block: BlockNumber,
+	pub end_block: BlockNumber,
+}
 
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
 #[derive(Encode, Decode)]
-pub struct Kitty(pub [u8; 16]);
+pub struct EnglishAuction<Balance, BlockNumber> {
+	pub reserve: Balance,
+	pub end_block: BlockNumber,
+}
+
+/// A proposal to swap the kitty of the proposer with the kitty of another owner
+#[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
+#[derive(Encode, Decode)]
+pub struct SwapProposal<AccountId, KittyIndex> {
+	pub proposer: AccountId,
+	pub proposer_kitty: KittyIndex,
+	pub target_kitty: KittyIndex,
+}
+
+/// Errors of the kitties module
+#[cfg_attr(feature = "std", derive(Debug))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+	/// Kitty operations are paused
+	Paused,
+	/// Sender is not allowed to create or breed kitties
+	NotMinter,
+	/// Kitties count reached the max value of the kitty index
+	CountOverflow,
+	/// Kitty does not exist
+	InvalidKitty,
+	/// Breeding a kitty with itself
+	SameParents,
+	/// Sender or the given account is not the owner of the kitty
+	NotOwner,
+	/// Sender is not approved to transfer or approve the kitty
+	NotApproved,
+	/// Approving self as operator
+	ApproveSelf,
+	/// Kitty is not listed for sale
+	NotForSale,
+	/// Kitty is listed for sale
+	KittyListed,
+	/// Offered price or bid is too low
+	PriceTooLow,
+	/// Not enough free balance to pay
+	InsufficientBalance,
+	/// Kitty is in auction
+	InAuction,
+	/// Kitty is not in auction
+	NotInAuction,
+	/// Auction start price is lower than end price
+	InvalidAuctionPrice,
+	/// Auction duration is zero or ends in the past
+	InvalidAuctionDuration,
+	/// Auction already ended
+	AuctionEnded,
+	/// Owner bidding or making offer for own kitty
+	OwnerCannotBid,
+	/// Offer does not exist
+	OfferNotFound,
+	/// Kitty is not available for siring
+	NotForSiring,
+	/// Too many kitties in a batch
+	TooManyKitties,
+	/// The same kitty appears more than once in a batch
+	DuplicatedKitty,
+	/// Seed is too long
+	SeedTooLong,
+	/// Sender and recipient are the same account
+	TransferToSelf,
+	/// Listing has expired
+	ListingExpired,
+	/// Listing expiry is in the past
+	InvalidListingExpiry,
+	/// Swap proposal does not exist
+	SwapNotFound,
+	/// Kitty owner records disagree
+	InconsistentOwner,
+	/// Total number of kitties reached `Trait::MaxKitties`
+	MaxSupplyReached,
+	/// Kitty has no breeding charges left
+	NoChargesLeft,
+	/// Listed price differs from the expected price
+	PriceChanged,
+	/// A parent is listed for sale
+	BreedListed,
+	/// A parent is still cooling down from the last breeding
+	BreedCooldown,
+	/// Kitty is locked by the owner
+	KittyLocked,
+	/// The child would exceed the maximum generation
+	MaxGenerationReached,
+	/// Paying would leave the buyer below the existential deposit
+	WouldKillAccount,
+	/// A payment is too low to create the receiving account
+	PaymentBelowMinimum,
+	/// The account that listed the kitty is no longer approved by the owner
+	ListerNotApproved,
+	/// The kitty is not owned by the null account
+	NotAbandoned,
+	/// The kitties share an ancestor within the inbreeding depth
+	KittiesRelated,
+	/// Metadata URI is longer than `MAX_METADATA_LENGTH`
+	MetadataTooLong,
+	/// Paying the breeding fee would leave the breeder below the existential deposit
+	InsufficientBalanceToBreed,
+	/// The account is frozen from trading
+	AccountFrozen,
+	/// The faucet was claimed too recently
+	FaucetCooldown,
+	/// Name is longer than `MAX_NAME_LENGTH`
+	NameTooLong,
+	/// Name is used by another kitty
+	NameTaken,
+	/// Name is empty
+	EmptyName,
+	/// Memo is longer than `MAX_MEMO_LENGTH`
+	MemoTooLong,
+	/// Buyer already owns the kitty
+	BuyOwnKitty,
+	/// Resetting the cooldown of a kitty that can already breed
+	NotOnCooldown,
+	/// A storage migration is in progress
+	MigrationInProgress,
+}
+
+impl Error {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Error::Paused => "Module is paused",
+			Error::NotMinter => "Not allowed to mint",
+			Error::CountOverflow => "Kitties count overflow",
+			Error::InvalidKitty => "Kitty does not exist",
+			Error::SameParents => "Needs different parent",
+			Error::NotOwner => "Not owner of kitty",
+			Error::NotApproved => "Not approved to transfer kitty",
+			Error::ApproveSelf => "Cannot approve self as operator",
+			Error::NotForSale => "Kitty not for sale",
+			Error::KittyListed => "Kitty is listed for sale",
+			Error::PriceTooLow => "Price is too low",
+			Error::InsufficientBalance => "Not enough balance",
+			Error::InAuction => "Kitty is in auction",
+			Error::NotInAuction => "Kitty not in auction",
+			Error::InvalidAuctionPrice => "Start price is lower than end price",
+			Error::InvalidAuctionDuration => "Invalid auction duration",
+			Error::AuctionEnded => "Auction ended",
+			Error::OwnerCannotBid => "Owner cannot bid",
+			Error::OfferNotFound => "Offer does not exist",
+			Error::NotForSiring => "Kitty not available for siring",
+			Error::TooManyKitties => "Too many kitties in batch",
+			Error::DuplicatedKitty => "Duplicated kitty in batch",
+			Error::SeedTooLong => "Seed is too long",
+			Error::TransferToSelf => "Cannot transfer to self",
+			Error::ListingExpired => "Listing expired",
+			Error::InvalidListingExpiry => "Listing expiry is in the past",
+			Error::SwapNotFound => "Swap proposal does not exist",
+			Error::InconsistentOwner => "Kitty ownership is inconsistent",
+			Error::MaxSupplyReached => "Max supply reached",
+			Error::NoChargesLeft => "No breeding charges left",
+			Error::PriceChanged => "Price does not match expected price",
+			Error::BreedListed => "Cannot breed a listed kitty",
+			Error::BreedCooldown => "Kitty is cooling down",
+			Error::KittyLocked => "Kitty is locked",
+			Error::MaxGenerationReached => "Max generation reached",
+			Error::WouldKillAccount => "Transfer would kill account",
+			Error::PaymentBelowMinimum => "Payment below existential deposit",
+			Error::ListerNotApproved => "Lister is no longer approved",
+			Error::NotAbandoned => "Kitty is not abandoned",
+			Error::KittiesRelated => "Kitties are related",
+			Error::MetadataTooLong => "Metadata is too long",
+			Error::InsufficientBalanceToBreed => "Insufficient balance to breed",
+			Error::AccountFrozen => "Account is frozen",
+			Error::FaucetCooldown => "Faucet claimed too recently",
+			Error::NameTooLong => "Name is too long",
+			Error::NameTaken => "Name is already taken",
+			Error::EmptyName => "Name cannot be empty",
+			Error::MemoTooLong => "Memo is too long",
+			Error::BuyOwnKitty => "Cannot buy own kitty",
+			Error::NotOnCooldown => "Kitty is not cooling down",
+			Error::MigrationInProgress => "Storage migration in progress",
+		}
+	}
+}
+
+impl From<Error> for &'static str {
+	fn from(err: Error) -> &'static str {
+		err.as_str()
+	}
+}
 
+type KittyOf<T> = Kitty<<T as system::Trait>::BlockNumber>;
+type DutchAuctionOf<T> = DutchAuction<MarketBalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type EnglishAuctionOf<T> = EnglishAuction<MarketBalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type SwapProposalOf<T> = SwapProposal<<T as system::Trait>::AccountId, <T as Trait>::KittyIndex>;
 type KittyLinkedItem<T> = LinkedItem<<T as Trait>::KittyIndex>;
-type OwnedKittiesList<T> = LinkedList<OwnedKitties<T>, <T as system::Trait>::AccountId, <T as Trait>::KittyIndex>;
+type OwnedKittiesList<T> = LinkedList<OwnedKitties<T>, OwnedKittiesCount<T>, <T as system::Trait>::AccountId, <T as Trait>::KittyIndex>;
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Kitties {
+		/// Version of the storage layout, new chains start with the latest version
+		pub StorageVersion get(storage_version) build(|_: &GenesisConfig<T>| STORAGE_VERSION): u32;
+		/// Progress of an ongoing storage migration. (next kitty index, end kitty index, paused before the migration)
+		pub MigrationCursor get(migration_cursor): Option<(u64, u64, bool)>;
+
 		/// Stores all the kitties, key is the kitty id / index
-		pub Kitties get(kitty): map T::KittyIndex => Option<Kitty>;
+		pub Kitties get(kitty): map T::KittyIndex => Option<KittyOf<T>>;
 		/// Stores the total number of kitties. i.e. the next kitty index
 		pub KittiesCount get(kitties_count): T::KittyIndex;
+		/// Ids of destroyed kitties to reuse, if `RecycleIds` is enabled
+		pub FreeIds get(free_ids): Vec<T::KittyIndex>;
+		/// Get the first owner of a kitty, who receives royalties on each sale
+		pub KittyOriginalBreeder get(breeder): map T::KittyIndex => T::AccountId;
+		/// Get the number of kitties created or bred by an account, never decreased
+		pub CreatedCount get(created_count): map T::AccountId => u32;
+		/// Get the number of times a kitty can still be a parent
+		pub KittyCharges get(charges): map T::KittyIndex => u32;
+		/// Get the block number from which a kitty can breed again
+		pub KittyCooldownUntil get(cooldown_until): map T::KittyIndex => T::BlockNumber;
+		/// Get the metadata URI of a kitty, pointing to off-chain art
+		pub KittyMetadata get(metadata): map T::KittyIndex => Option<Vec<u8>>;
+		/// Get the name of a kitty
+		pub KittyNames get(kitty_name): map T::KittyIndex => Option<Vec<u8>>;
+		/// Get the kitty using a name. Names are unique.
+		pub NameOwner get(name_owner): map Vec<u8> => Option<T::KittyIndex>;
+		/// Get the parents of a bred kitty
+		pub KittyParents get(parents): map T::KittyIndex => Option<(T::KittyIndex, T::KittyIndex)>;
+		/// Get the children bred by a pair of kitties, lower id first
+		pub ChildrenOf get(children_of): map (T::KittyIndex, T::KittyIndex) => Vec<T::KittyIndex>;
+		/// Get the number of children a kitty is a parent of
+		pub OffspringCount get(offspring_count): map T::KittyIndex => u32;
+
+		/// Whether all kitty operations are paused
+		pub Paused get(paused): bool;
+
+		/// Whether only approved minters can create and breed kitties
+		pub MintingRestricted get(minting_restricted): bool;
+		/// Get whether an account is approved to mint while minting is restricted
+		pub Minters get(is_minter): map T::AccountId => bool;
+		/// Get whether an account is frozen from creating, breeding and trading kitties
+		pub FrozenAccounts get(is_frozen): map T::AccountId => bool;
+		/// Get the block of the last faucet claim of an account
+		pub LastClaim get(last_claim): map T::AccountId => Option<T::BlockNumber>;
+
+		/// Incremented on each random value generation so they never collide
+		pub Nonce get(nonce): u64;
 
 		/// Get kitty ownership. Stored in a linked map.
 		pub OwnedKitties get(owned_kitties): map (T::AccountId, Option<T::KittyIndex>) => Option<KittyLinkedItem<T>>;
+		/// Get number of kitties owned by an account.
+		pub OwnedKittiesCount get(owned_kitties_count): map T::AccountId => u64;
 
 		/// Get kitty owner
 		pub KittyOwners get(kitty_owner): map T::KittyIndex => Option<T::AccountId>;
 
 		/// Get kitty price. None means not for sale.
-		pub KittyPrices get(kitty_price): map T::KittyIndex => Option<BalanceOf<T>>
+		pub KittyPrices get(kitty_price): map T::KittyIndex => Option<MarketBalanceOf<T>>;
+		/// Kitties currently listed for sale
+		pub ForSale get(for_sale): Vec<T::KittyIndex>;
+		/// Get the last block number at which a listed kitty can be bought
+		pub KittyListingExpiry get(listing_expiry): map T::KittyIndex => Option<T::BlockNumber>;
+		/// Listed kitties by expiry block number, to be removed once expired
+		pub ListingsExpiringAt get(listings_expiring_at): map T::BlockNumber => Vec<T::KittyIndex>;
+		/// Get deposit reserved from the owner for listing a kitty
+		pub KittyListingDeposits get(listing_deposit): map T::KittyIndex => Option<BalanceOf<T>>;
+		/// Get the account that listed a kitty for sale, the owner or an approved operator
+		pub KittyListers get(lister): map T::KittyIndex => Option<T::AccountId>;
+
+		/// Get kitty Dutch auction. None means not in auction.
+		pub KittyAuctions get(auction): map T::KittyIndex => Option<DutchAuctionOf<T>>;
+
+		/// Get kitty English auction. None means not in auction.
+		pub EnglishAuctions get(english_auction): map T::KittyIndex => Option<EnglishAuctionOf<T>>;
+		/// Kitties in English auction by end block, to be settled once the auction ended
+		pub AuctionsEndingAt get(ending): map T::BlockNumber => Vec<T::KittyIndex>;
+		/// Get highest bid of an English auction. (bidder, amount)
+		pub HighestBid get(highest_bid): map T::KittyIndex => Option<(T::AccountId, MarketBalanceOf<T>)>;
+
+		/// Get offer made for a kitty. The offered amount is reserved.
+		pub KittyOffers get(offers): map (T::KittyIndex, T::AccountId) => Option<MarketBalanceOf<T>>;
+		/// Get the accounts with an offer for a kitty
+		pub KittyOfferers get(offerers): map T::KittyIndex => Vec<T::AccountId>;
+
+		/// Get kitty siring price. None means not available for siring.
+		pub SireRights get(sire_price): map T::KittyIndex => Option<MarketBalanceOf<T>>;
+
+		/// Get account approved to transfer a kitty
+		pub KittyApprovals get(approved): map T::KittyIndex => Option<T::AccountId>;
+		/// Get whether a kitty is locked by the owner against transfer, sale and breeding
+		pub KittyLocked get(is_locked): map T::KittyIndex => bool;
+		/// Get whether an operator is approved to transfer all kitties of an owner. (owner, operator)
+		pub OperatorApprovals get(is_operator): map (T::AccountId, T::AccountId) => bool;
+
+		/// Get pending swap proposal
+		pub SwapProposals get(swap_proposal): map u64 => Option<SwapProposalOf<T>>;
+		/// Get the ids of pending swap proposals involving a kitty
+		pub KittySwapProposals get(swap_proposals_of): map T::KittyIndex => Vec<u64>;
+		/// Id of the next swap proposal
+		pub NextSwapId get(next_swap_id): u64;
+	}
+	add_extra_genesis {
+		/// Kitties to create at genesis. (owner, dna)
+		config(kitties): Vec<(T::AccountId, Vec<u8>)>;
+
+		build(|storage: &mut runtime_primitives::StorageOverlay, _: &mut runtime_primitives::ChildrenStorageOverlay, config: &GenesisConfig<T>| {
+			with_storage(storage, || {
+				for (owner, dna) in config.kitties.iter() {
+					assert!(dna.len() == T::DnaLength::get() as usize, "Invalid kitty DNA length at genesis");
+					<Module<T>>::insert_kitty(owner, dna.clone(), 0).expect("Kitties count overflow at genesis");
+				}
+			});
+		});
 	}
 }
 
 decl_event!(
 	pub enum Event<T> where
 		<T as system::Trait>::AccountId,
+		<T as system::Trait>::BlockNumber,
 		<T as Trait>::KittyIndex,
-		Balance = BalanceOf<T>,
+		Balance = MarketBalanceOf<T>,
 	{
-		/// A kitty is created. (owner, kitty_id)
-		Created(AccountId, KittyIndex),
+		/// A kitty is created. (owner, kitty_id, dna)
+		Created(AccountId, KittyIndex, Vec<u8>),
+		/// A kitty is bred. (owner, kitty_id_1, kitty_id_2, new_kitty_id)
+		Bred(AccountId, KittyIndex, KittyIndex, KittyIndex),
 		/// A kitty is transferred. (from, to, kitty_id)
 		Transferred(AccountId, AccountId, KittyIndex),
+		/// A kitty is transferred by root without the owner's signature. (from, to, kitty_id)
+		ForceTransferred(AccountId, AccountId, KittyIndex),
+		/// A kitty is transferred with a memo, e.g. to identify a deposit. (from, to, kitty_id, memo)
+		TransferredWithMemo(AccountId, AccountId, KittyIndex, Vec<u8>),
+		/// A kitty is gifted. (from, to, kitty_id)
+		Gifted(AccountId, AccountId, KittyIndex),
+		/// A kitty is abandoned by the owner. (owner, kitty_id)
+		Abandoned(AccountId, KittyIndex),
+		/// An abandoned kitty is adopted. (new_owner, kitty_id)
+		Adopted(AccountId, KittyIndex),
+		/// Breeding failed as a kitty is cooling down. (kitty_id, cooldown_until)
+		BreedFailedCooldown(KittyIndex, BlockNumber),
+		/// The breeding cooldown of a kitty is reset. (owner, kitty_id)
+		CooldownReset(AccountId, KittyIndex),
+		/// A kitty is destroyed. (owner, kitty_id)
+		Destroyed(AccountId, KittyIndex),
+		/// The kitties owned by an account changed. (owner)
+		CollectionChanged(AccountId),
 		/// A kitty is available for sale. (owner, kitty_id, price)
-		Ask(AccountId, KittyIndex, Option<Balance>),
-		/// A kitty is sold. (from, to, kitty_id, price)
-		Sold(AccountId, AccountId, KittyIndex, Balance),
+		Ask(AccountId, KittyIndex, Balance),
+		/// The price of a listed kitty is changed. (owner, kitty_id, price)
+		Relisted(AccountId, KittyIndex, Balance),
+		/// A kitty is no longer for sale. (owner, kitty_id)
+		Delisted(AccountId, KittyIndex),
+		/// A kitty is sold. (from, to, kitty_id, price, offered)
+		/// The buyer may offer more than the price actually paid.
+		Sold(AccountId, AccountId, KittyIndex, Balance, Balance),
+		/// A Dutch auction is started. (owner, kitty_id, start_price, end_price)
+		AuctionStarted(AccountId, KittyIndex, Balance, Balance),
+		/// A Dutch auction is cancelled. (owner, kitty_id)
+		AuctionCancelled(AccountId, KittyIndex),
+		/// An English auction is started. (owner, kitty_id, reserve, end_block)
+		EnglishAuctionStarted(AccountId, KittyIndex, Balance, BlockNumber),
+		/// A bid is placed in English auction. (bidder, kitty_id, amount)
+		Bid(AccountId, KittyIndex, Balance),
+		/// An English auction is ended without any bid. (owner, kitty_id)
+		EnglishAuctionEnded(AccountId, KittyIndex),
+		/// An offer is made for a kitty. (offerer, kitty_id, amount)
+		OfferMade(AccountId, KittyIndex, Balance),
+		/// An offer is withdrawn. (offerer, kitty_id)
+		OfferWithdrawn(AccountId, KittyIndex),
+		/// A kitty is available for siring. (owner, kitty_id, price)
+		SireOffered(AccountId, KittyIndex, Option<Balance>),
+		/// An account is approved to transfer a kitty. (owner, approved, kitty_id)
+		Approval(AccountId, AccountId, KittyIndex),
+		/// An operator is approved or disapproved for all kitties of an owner. (owner, operator, approved)
+		ApprovalForAll(AccountId, AccountId, bool),
+		/// The metadata URI of a kitty is set. (owner, kitty_id, uri)
+		MetadataSet(AccountId, KittyIndex, Vec<u8>),
+		/// The metadata URI of a kitty is cleared. (owner, kitty_id)
+		MetadataCleared(AccountId, KittyIndex),
+		/// A kitty is named. (owner, kitty_id, name)
+		NameSet(AccountId, KittyIndex, Vec<u8>),
+		/// A kitty is locked or unlocked. (owner, kitty_id, locked)
+		LockSet(AccountId, KittyIndex, bool),
+		/// The module is paused or unpaused. (paused)
+		Paused(bool),
+		/// An account is allowed or disallowed to mint. (who, allowed)
+		MinterSet(AccountId, bool),
+		/// An account is frozen or unfrozen. (who, frozen)
+		AccountFrozen(AccountId, bool),
+		/// Minting is restricted or opened. (restricted)
+		MintingRestricted(bool),
+		/// A swap is proposed. (proposer, proposal_id, proposer_kitty, target_kitty)
+		SwapProposed(AccountId, u64, KittyIndex, KittyIndex),
+		/// A swap proposal is cancelled. (proposer, proposal_id)
+		SwapCancelled(AccountId, u64),
+		/// Two kitties are swapped. (proposer, accepter, proposer_kitty, target_kitty)
+		Swapped(AccountId, AccountId, KittyIndex, KittyIndex),
 	}
 );
 
@@ -62,482 +563,5593 @@ decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event<T>() = default;
 
+		fn on_initialize(n: T::BlockNumber) {
+			if Self::storage_version() < STORAGE_VERSION {
+				Self::migrate();
+			}
+			Self::remove_expired_listings(n);
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			Self::settle_english_auctions(n);
+		}
+
+		/// Pause or unpause all kitty operations
+		pub fn set_paused(origin, paused: bool) {
+			ensure_root(origin)?;
+			// The module stays paused until the migration is done
+			ensure!(Self::migration_cursor().is_none(), Error::MigrationInProgress.into());
+
+			<Paused<T>>::put(paused);
+
+			Self::deposit_event(RawEvent::Paused(paused));
+		}
+
+		/// Allow or disallow an account to mint kitties while minting is restricted
+		pub fn set_minter(origin, who: T::AccountId, allowed: bool) {
+			ensure_root(origin)?;
+
+			if allowed {
+				<Minters<T>>::insert(&who, true);
+			} else {
+				<Minters<T>>::remove(&who);
+			}
+
+			Self::deposit_event(RawEvent::MinterSet(who, allowed));
+		}
+
+		/// Freeze or unfreeze an account from creating, breeding and trading kitties
+		pub fn set_frozen(origin, who: T::AccountId, frozen: bool) {
+			ensure_root(origin)?;
+
+			if frozen {
+				<FrozenAccounts<T>>::insert(&who, true);
+			} else {
+				<FrozenAccounts<T>>::remove(&who);
+			}
+
+			Self::deposit_event(RawEvent::AccountFrozen(who, frozen));
+		}
+
+		/// Transfer a kitty without the owner's signature, to resolve disputes.
+		/// Any listing and lock of the kitty are cleared.
+		pub fn force_transfer(origin, from: T::AccountId, to: T::AccountId, kitty_id: T::KittyIndex) {
+			ensure_root(origin)?;
+			ensure!(from != to, Error::TransferToSelf.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(from.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+
+			// Lock is set by the owner, and root can move kitties of frozen accounts
+			<KittyLocked<T>>::remove(kitty_id);
+			Self::move_kitty(&from, &to, kitty_id);
+
+			Self::deposit_event(RawEvent::ForceTransferred(from, to, kitty_id));
+		}
+
+		/// Restrict minting to approved minters or open it to everyone
+		pub fn set_minting_restricted(origin, restricted: bool) {
+			ensure_root(origin)?;
+
+			<MintingRestricted<T>>::put(restricted);
+
+			Self::deposit_event(RawEvent::MintingRestricted(restricted));
+		}
+
 		/// Create a new kitty
 		pub fn create(origin) {
 			let sender = ensure_signed(origin)?;
-			let kitty_id = Self::next_kitty_id()?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
 
-			// Generate a random 128bit value
+			// Make sure the kitty can be created before charging the fee
+			Self::next_kitty_id()?;
+
+			Self::charge_creation_fee(&sender)?;
+
+			// Generate random DNA
 			let dna = Self::random_value(&sender);
 
 			// Create and store kitty
-			let kitty = Kitty(dna);
-			Self::insert_kitty(&sender, kitty_id, kitty);
+			let kitty_id = Self::insert_kitty(&sender, dna.clone(), 0)?;
+
+			Self::deposit_event(RawEvent::Created(sender, kitty_id, dna));
+		}
+
+		/// Create a new kitty with DNA derived from a user supplied seed
+		pub fn create_with_seed(origin, seed: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
+
+			ensure!(seed.len() <= MAX_SEED_LENGTH, Error::SeedTooLong.into());
+
+			// Make sure the kitty can be created before charging the fee
+			Self::next_kitty_id()?;
+
+			Self::charge_creation_fee(&sender)?;
+
+			// Generate random DNA with the seed
+			let dna = Self::random_value_with_seed(&sender, &seed);
+
+			// Create and store kitty
+			let kitty_id = Self::insert_kitty(&sender, dna.clone(), 0)?;
+
+			Self::deposit_event(RawEvent::Created(sender, kitty_id, dna));
+		}
+
+		/// Create multiple new kitties
+		pub fn create_batch(origin, count: u32) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
+
+			ensure!(count <= T::MaxCreateBatchSize::get(), Error::TooManyKitties.into());
+
+			// Check the whole batch up front, a failing batch must not mint or charge anything
+			Self::ensure_can_create(count)?;
+			let fee = T::CreationFee::get().checked_mul(&<BalanceOf<T> as As<u64>>::sa(count as u64))
+				.ok_or(Error::InsufficientBalance)?;
+			Self::charge_fee(&sender, fee)?;
+
+			for _ in 0..count {
+				// Generate random DNA
+				let dna = Self::random_value(&sender);
+
+				let kitty_id = Self::insert_kitty(&sender, dna.clone(), 0)?;
 
-			Self::deposit_event(RawEvent::Created(sender, kitty_id));
+				Self::deposit_event(RawEvent::Created(sender.clone(), kitty_id, dna));
+			}
+		}
+
+		/// Create a new kitty owned by another account, for airdrops
+		/// No creation fee is charged
+		pub fn create_for(origin, to: T::AccountId) {
+			ensure_root(origin)?;
+
+			let dna = Self::random_value(&to);
+
+			let kitty_id = Self::insert_kitty(&to, dna.clone(), 0)?;
+
+			Self::deposit_event(RawEvent::Created(to, kitty_id, dna));
+		}
+
+		/// Claim a free kitty from the faucet, once every `FaucetCooldown` blocks
+		pub fn claim_faucet(origin) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
+
+			let now = <system::Module<T>>::block_number();
+			if let Some(last) = Self::last_claim(&sender) {
+				ensure!(now >= last + T::FaucetCooldown::get(), Error::FaucetCooldown.into());
+			}
+
+			let dna = Self::random_value(&sender);
+
+			let kitty_id = Self::insert_kitty(&sender, dna.clone(), 0)?;
+
+			<LastClaim<T>>::insert(&sender, now);
+
+			Self::deposit_event(RawEvent::Created(sender, kitty_id, dna));
 		}
 
 		/// Breed kitties
 		pub fn breed(origin, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) {
 			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
 
-			let new_kitty_id = Self::do_breed(&sender, kitty_id_1, kitty_id_2)?;
+			for new_kitty_id in Self::do_breed(&sender, kitty_id_1, kitty_id_2)? {
+				Self::deposit_event(RawEvent::Bred(sender.clone(), kitty_id_1, kitty_id_2, new_kitty_id));
+			}
+		}
+
+		/// Pay to end the breeding cooldown of a kitty
+		pub fn reset_cooldown(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
 
-			Self::deposit_event(RawEvent::Created(sender, new_kitty_id));
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			let now = <system::Module<T>>::block_number();
+			ensure!(Self::cooldown_until(kitty_id) > now, Error::NotOnCooldown.into());
+			Self::ensure_not_frozen(&sender)?;
+
+			Self::charge_fee(&sender, T::CooldownResetFee::get())?;
+
+			<KittyCooldownUntil<T>>::insert(kitty_id, now);
+
+			Self::deposit_event(RawEvent::CooldownReset(sender, kitty_id));
 		}
 
 		/// Transfer a kitty to new owner
 		pub fn transfer(origin, to: T::AccountId, kitty_id: T::KittyIndex) {
 			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(sender != to, Error::TransferToSelf.into());
 
-			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), "Only owner can transfer kitty");
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
 			
-			Self::do_transfer(&sender, &to, kitty_id);
+			Self::do_transfer(&sender, &to, kitty_id)?;
 
 			Self::deposit_event(RawEvent::Transferred(sender, to, kitty_id));
 		}
 
-		/// Set a price for a kitty for sale
-		/// None to delist the kitty
-		pub fn ask(origin, kitty_id: T::KittyIndex, price: Option<BalanceOf<T>>) {
+		/// Transfer a kitty to new owner with a memo. The memo is not stored.
+		pub fn transfer_with_memo(origin, to: T::AccountId, kitty_id: T::KittyIndex, memo: Vec<u8>) {
 			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(sender != to, Error::TransferToSelf.into());
+			ensure!(memo.len() <= MAX_MEMO_LENGTH, Error::MemoTooLong.into());
 
-			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), "Only owner can set price for kitty");
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
 
-			if let Some(ref price) = price {
-				<KittyPrices<T>>::insert(kitty_id, price);
-			} else {
-				<KittyPrices<T>>::remove(kitty_id);
-			}
-			
-			Self::deposit_event(RawEvent::Ask(sender, kitty_id, price));
+			Self::do_transfer(&sender, &to, kitty_id)?;
+
+			Self::deposit_event(RawEvent::TransferredWithMemo(sender, to, kitty_id, memo));
 		}
 
-		/// Buy a kitty with max price willing to pay
-		pub fn buy(origin, kitty_id: T::KittyIndex, price: BalanceOf<T>) {
+		/// Gift a kitty to another account
+		/// Any listing of the kitty is cleared
+		pub fn gift(origin, to: T::AccountId, kitty_id: T::KittyIndex) {
 			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
 
-			let owner = Self::kitty_owner(kitty_id);
-			ensure!(owner.is_some(), "Kitty does not exist");
-			let owner = owner.unwrap();
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
 
-			let kitty_price = Self::kitty_price(kitty_id);
-			ensure!(kitty_price.is_some(), "Kitty not for sale");
+			Self::do_transfer(&sender, &to, kitty_id)?;
 
-			let kitty_price = kitty_price.unwrap();
-			ensure!(price >= kitty_price, "Price is too low");
+			Self::deposit_event(RawEvent::Gifted(sender, to, kitty_id));
+		}
 
-			T::Currency::transfer(&sender, &owner, kitty_price)?;
+		/// Give up a kitty. It is owned by the null account until adopted by anyone.
+		/// Any listing of the kitty is cleared
+		pub fn abandon(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
 
-			<KittyPrices<T>>::remove(kitty_id);
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
 
-			Self::do_transfer(&owner, &sender, kitty_id);
+			Self::do_transfer(&sender, &T::NullAccount::get(), kitty_id)?;
 
-			Self::deposit_event(RawEvent::Sold(owner, sender, kitty_id, kitty_price));
+			Self::deposit_event(RawEvent::Abandoned(sender, kitty_id));
 		}
-	}
-}
 
-fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
-	((selector & dna1) | (!selector & dna2))
-}
+		/// Adopt an abandoned kitty
+		pub fn adopt(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
 
-impl<T: Trait> Module<T> {
-	fn random_value(sender: &T::AccountId) -> [u8; 16] {
-		let payload = (<system::Module<T>>::random_seed(), sender, <system::Module<T>>::extrinsic_index(), <system::Module<T>>::block_number());
-		payload.using_encoded(blake2_128)
-	}
+			let null_account = T::NullAccount::get();
+			ensure!(Self::kitty_owner(kitty_id) == Some(null_account.clone()), Error::NotAbandoned.into());
+			ensure!(sender != null_account, Error::TransferToSelf.into());
 
-	fn next_kitty_id() -> result::Result<T::KittyIndex, &'static str> {
-		let kitty_id = Self::kitties_count();
-		if kitty_id == <T::KittyIndex as Bounded>::max_value() {
-			return Err("Kitties count overflow");
+			Self::do_transfer(&null_account, &sender, kitty_id)?;
+
+			Self::deposit_event(RawEvent::Adopted(sender, kitty_id));
 		}
-		Ok(kitty_id)
-	}
 
-	fn insert_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
-		<OwnedKittiesList<T>>::append(owner, kitty_id);
-	}
+		/// Destroy a kitty permanently
+		/// Any listing of the kitty is cleared
+		pub fn destroy(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
 
-	fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: Kitty) {
-		// Create and store kitty
-		<Kitties<T>>::insert(kitty_id, kitty);
-		<KittiesCount<T>>::put(kitty_id + One::one());
-		<KittyOwners<T>>::insert(kitty_id, owner.clone());
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
 
-		Self::insert_owned_kitty(owner, kitty_id);
-	}
+			Self::remove_listing(&sender, kitty_id);
+			<OwnedKittiesList<T>>::remove(&sender, kitty_id);
+			<KittyOwners<T>>::remove(kitty_id);
+			<Kitties<T>>::remove(kitty_id);
+			<KittyOriginalBreeder<T>>::remove(kitty_id);
+			<KittyCharges<T>>::remove(kitty_id);
+			<KittyCooldownUntil<T>>::remove(kitty_id);
+			<KittyMetadata<T>>::remove(kitty_id);
+			if let Some(name) = <KittyNames<T>>::take(kitty_id) {
+				<NameOwner<T>>::remove(name);
+			}
+			if let Some((parent_1, parent_2)) = <KittyParents<T>>::take(kitty_id) {
+				<ChildrenOf<T>>::mutate(parent_pair(parent_1, parent_2), |children| children.retain(|id| *id != kitty_id));
+			}
+			// Children keep pointing at their destroyed parent
+			let has_offspring = <OffspringCount<T>>::take(kitty_id) > 0;
+			<KittyAuctions<T>>::remove(kitty_id);
+			<SireRights<T>>::remove(kitty_id);
+			<KittyApprovals<T>>::remove(kitty_id);
+			Self::refund_offers(kitty_id);
+			for proposal_id in <KittySwapProposals<T>>::take(kitty_id) {
+				Self::remove_swap_proposal(proposal_id);
+			}
 
-	fn do_breed(sender: &T::AccountId, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> result::Result<T::KittyIndex, &'static str> {
-		let kitty1 = Self::kitty(kitty_id_1);
-		let kitty2 = Self::kitty(kitty_id_2);
+			// A new kitty must not inherit the family tree of the destroyed one
+			if T::RecycleIds::get() && !has_offspring {
+				<FreeIds<T>>::mutate(|ids| ids.push(kitty_id));
+			}
 
-		ensure!(kitty1.is_some(), "Invalid kitty_id_1");
-		ensure!(kitty2.is_some(), "Invalid kitty_id_2");
-		ensure!(kitty_id_1 != kitty_id_2, "Needs different parent");
-		ensure!(Self::kitty_owner(&kitty_id_1).map(|owner| owner == *sender).unwrap_or(false), "Not owner of kitty1");
-		ensure!(Self::kitty_owner(&kitty_id_2).map(|owner| owner == *sender).unwrap_or(false), "Not owner of kitty2");
+			Self::deposit_event(RawEvent::CollectionChanged(sender.clone()));
 
-		let kitty_id = Self::next_kitty_id()?;
+			Self::deposit_event(RawEvent::Destroyed(sender, kitty_id));
+		}
 
-		let kitty1_dna = kitty1.unwrap().0;
-		let kitty2_dna = kitty2.unwrap().0;
+		/// Transfer multiple kitties to new owner
+		/// Either all kitties are transferred or none of them
+		pub fn transfer_batch(origin, to: T::AccountId, kitty_ids: Vec<T::KittyIndex>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
 
-		// Generate a random 128bit value
-		let selector = Self::random_value(&sender);
-		let mut new_dna = [0u8; 16];
+			ensure!(kitty_ids.len() as u32 <= T::MaxBatchSize::get(), Error::TooManyKitties.into());
 
-		// Combine parents and selector to create new kitty
-		for i in 0..kitty1_dna.len() {
-			new_dna[i] = combine_dna(kitty1_dna[i], kitty2_dna[i], selector[i]);
-		}
+			let mut unique_ids = kitty_ids.clone();
+			unique_ids.sort();
+			unique_ids.dedup();
+			ensure!(unique_ids.len() == kitty_ids.len(), Error::DuplicatedKitty.into());
 
-		Self::insert_kitty(sender, kitty_id, Kitty(new_dna));
+			for kitty_id in &kitty_ids {
+				ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(*kitty_id))), Error::NotOwner.into());
+				ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+				ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+			}
 
-		Ok(kitty_id)
-	}
+			for kitty_id in kitty_ids {
+				Self::do_transfer(&sender, &to, kitty_id)?;
 
-	fn do_transfer(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex)  {
-		<OwnedKittiesList<T>>::remove(&from, kitty_id);
-		<OwnedKittiesList<T>>::append(&to, kitty_id);
-		<KittyOwners<T>>::insert(kitty_id, to);
-	}
-}
+				Self::deposit_event(RawEvent::Transferred(sender.clone(), to.clone(), kitty_id));
+			}
+		}
 
-/// Tests for Kitties module
-#[cfg(test)]
-mod tests {
-	use super::*;
+		/// Set a price for a kitty for sale
+		/// None to delist the kitty
+		/// Can be called by the owner or an approved operator
+		/// The listing can be bought up to and including block `expires_at`
+		pub fn ask(origin, kitty_id: T::KittyIndex, price: Option<MarketBalanceOf<T>>, expires_at: Option<T::BlockNumber>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
 
-	use runtime_io::with_externalities;
-	use primitives::{H256, Blake2Hasher};
-	use support::{impl_outer_origin, assert_ok, assert_noop};
-	use runtime_primitives::{
-		BuildStorage,
-		traits::{BlakeTwo256, IdentityLookup},
-		testing::{Digest, DigestItem, Header}
-	};
+			// Approved operators can list on behalf of the owner
+			let owner = Self::kitty_owner(kitty_id).ok_or(Error::NotOwner)?;
+			ensure!(Self::is_approved_or_owner(&sender, &owner, kitty_id), Error::NotApproved.into());
+			Self::ensure_not_frozen(&owner)?;
+			ensure!(!Self::in_auction(kitty_id), Error::InAuction.into());
 
-	impl_outer_origin! {
-		pub enum Origin for Test {}
-	}
+			if let Some(price) = price {
+				ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+				ensure!(price >= T::MinListingPrice::get(), Error::PriceTooLow.into());
+				if let Some(expires_at) = expires_at {
+					ensure!(expires_at >= <system::Module<T>>::block_number(), Error::InvalidListingExpiry.into());
+				}
 
-	// For testing the module, we construct most of a mock runtime. This means
-	// first constructing a configuration type (`Test`) which `impl`s each of the
-	// configuration traits of modules we want to use.
-	#[derive(Clone, Eq, PartialEq, Debug)]
-	pub struct Test;
-	impl system::Trait for Test {
-		type Origin = Origin;
-		type Index = u64;
-		type BlockNumber = u64;
-		type Hash = H256;
-		type Hashing = BlakeTwo256;
-		type Digest = Digest;
-		type AccountId = u64;
-		type Lookup = IdentityLookup<Self::AccountId>;
-		type Header = Header;
-		type Event = ();
-		type Log = DigestItem;
-	}
-	impl balances::Trait for Test {
-		type Balance = u32;
-		type OnFreeBalanceZero = ();
-		type OnNewAccount = ();
-		type Event = ();
+				Self::list_kitty(&owner, &sender, kitty_id, price, expires_at)?;
+			} else {
+				Self::remove_listing(&owner, kitty_id);
 
-		type TransactionPayment = ();
-		type DustRemoval = ();
-		type TransferPayment = ();
-	}
-	impl Trait for Test {
-		type KittyIndex = u32;
-		type Currency = balances::Module<Test>;
-		type Event = ();
-	}
-	type Balances = balances::Module<Test>;
-	type KittyModule = Module<Test>;
-	type OwnedKittiesTest = OwnedKitties<Test>;
+				Self::deposit_event(RawEvent::Delisted(owner, kitty_id));
+			}
+		}
 
-	// This function basically just builds a genesis storage key/value store according to
-	// our desired mockup.
-	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
-		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
-		t.extend(balances::GenesisConfig::<Test> {
-			transaction_base_fee: 0,
-			transaction_byte_fee: 0,
-			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
-			existential_deposit: 0,
-			transfer_fee: 0,
-			creation_fee: 0,
-			vesting: vec![],
-		}.build_storage().unwrap().0);
-		t.into()
-	}
+		/// Change the price of a kitty already for sale, keeping its deposit and expiry
+		/// Can be called by the owner or an approved operator
+		pub fn relist(origin, kitty_id: T::KittyIndex, new_price: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
 
-	#[test]
-	fn owned_kitties_can_append_values() {
-		with_externalities(&mut new_test_ext(), || {
-			OwnedKittiesList::<Test>::append(&0, 1);
+			let owner = Self::kitty_owner(kitty_id).ok_or(Error::NotOwner)?;
+			ensure!(Self::is_approved_or_owner(&sender, &owner, kitty_id), Error::NotApproved.into());
+			ensure!(<KittyPrices<T>>::exists(kitty_id), Error::NotForSale.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+			ensure!(new_price >= T::MinListingPrice::get(), Error::PriceTooLow.into());
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: Some(1),
-				next: Some(1),
-			}));
+			<KittyPrices<T>>::insert(kitty_id, new_price);
+			<KittyListers<T>>::insert(kitty_id, &sender);
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
-				prev: None,
-				next: None,
-			}));
+			Self::deposit_event(RawEvent::Relisted(owner, kitty_id, new_price));
+		}
 
-			OwnedKittiesList::<Test>::append(&0, 2);
+		/// Set prices for multiple kitties of the sender
+		/// Either all kitties are listed or none of them
+		pub fn ask_batch(origin, items: Vec<(T::KittyIndex, MarketBalanceOf<T>)>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: Some(2),
-				next: Some(1),
-			}));
+			ensure!(items.len() as u32 <= T::MaxBatchSize::get(), Error::TooManyKitties.into());
+
+			let mut unique_ids: Vec<_> = items.iter().map(|(kitty_id, _)| *kitty_id).collect();
+			unique_ids.sort();
+			unique_ids.dedup();
+			ensure!(unique_ids.len() == items.len(), Error::DuplicatedKitty.into());
+
+			let mut new_deposits = 0u64;
+			for (kitty_id, price) in &items {
+				ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(*kitty_id))), Error::NotOwner.into());
+				ensure!(!Self::in_auction(*kitty_id), Error::InAuction.into());
+				ensure!(!Self::is_locked(*kitty_id), Error::KittyLocked.into());
+				ensure!(*price >= T::MinListingPrice::get(), Error::PriceTooLow.into());
+				if !<KittyListingDeposits<T>>::exists(kitty_id) {
+					new_deposits += 1;
+				}
+			}
+
+			// Make sure all deposits can be reserved before listing any kitty
+			let total_deposit = T::ListingDeposit::get() * <BalanceOf<T> as As<u64>>::sa(new_deposits);
+			ensure!(T::Currency::can_reserve(&sender, total_deposit), Error::InsufficientBalance.into());
+
+			for (kitty_id, price) in items {
+				Self::list_kitty(&sender, &sender, kitty_id, price, None)?;
+			}
+		}
+
+		/// Remove all kitties of the sender from sale
+		pub fn delist_all(origin) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			for kitty_id in Self::owned_kitties_iter(&sender) {
+				if <KittyPrices<T>>::exists(kitty_id) {
+					Self::remove_listing(&sender, kitty_id);
+					Self::deposit_event(RawEvent::Delisted(sender.clone(), kitty_id));
+				}
+			}
+		}
+
+		/// Buy a kitty with max price willing to pay
+		pub fn buy(origin, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			Self::can_buy(&sender, kitty_id, price)?;
+
+			Self::do_buy(&sender, kitty_id, price)?;
+		}
+
+		/// Buy a kitty only if it is listed at exactly `expected_price`
+		pub fn buy_exact(origin, kitty_id: T::KittyIndex, expected_price: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+
+			let kitty_price = Self::kitty_price(kitty_id);
+			ensure!(kitty_price.is_some(), Error::NotForSale.into());
+			ensure!(kitty_price == Some(expected_price), Error::PriceChanged.into());
+			Self::can_buy(&sender, kitty_id, expected_price)?;
+
+			Self::do_buy(&sender, kitty_id, expected_price)?;
+		}
+
+		/// Start a Dutch auction for a kitty
+		/// The price declines linearly from `start_price` to `end_price` over `duration` blocks
+		pub fn start_auction(origin, kitty_id: T::KittyIndex, start_price: MarketBalanceOf<T>, end_price: MarketBalanceOf<T>, duration: T::BlockNumber) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!Self::in_auction(kitty_id), Error::InAuction.into());
+			ensure!(Self::kitty_price(kitty_id).is_none(), Error::KittyListed.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+			ensure!(start_price >= end_price, Error::InvalidAuctionPrice.into());
+			ensure!(!duration.is_zero(), Error::InvalidAuctionDuration.into());
+
+			let start_block = <system::Module<T>>::block_number();
+			let end_block = start_block.checked_add(&duration).ok_or(Error::InvalidAuctionDuration)?;
+			<KittyAuctions<T>>::insert(kitty_id, DutchAuction {
+				start_price,
+				end_price,
+				start_block,
+				end_block,
+			});
+
+			Self::deposit_event(RawEvent::AuctionStarted(sender, kitty_id, start_price, end_price));
+		}
+
+		/// Cancel a running Dutch auction
+		pub fn cancel_auction(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(<KittyAuctions<T>>::exists(kitty_id), Error::NotInAuction.into());
+
+			<KittyAuctions<T>>::remove(kitty_id);
+
+			Self::deposit_event(RawEvent::AuctionCancelled(sender, kitty_id));
+		}
+
+		/// Buy a kitty in Dutch auction at the current price with max price willing to pay
+		pub fn bid_auction(origin, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let owner = Self::kitty_owner(kitty_id);
+			ensure!(owner.is_some(), Error::InvalidKitty.into());
+			let owner = owner.unwrap();
+
+			let auction_price = Self::auction_price(kitty_id);
+			ensure!(auction_price.is_some(), Error::NotInAuction.into());
+
+			let auction_price = auction_price.unwrap();
+			ensure!(owner != sender, Error::BuyOwnKitty.into());
+			ensure!(price >= auction_price, Error::PriceTooLow.into());
+
+			// Auction sales pay the marketplace fee and royalty like any other sale
+			Self::ensure_can_pay(&sender, &owner, kitty_id, auction_price, false)?;
+			Self::pay_for_kitty(&sender, &owner, kitty_id, auction_price, false)?;
+
+			Self::do_transfer(&owner, &sender, kitty_id)?;
+
+			Self::deposit_event(RawEvent::Sold(owner, sender, kitty_id, auction_price, price));
+		}
+
+		/// Start an English auction for a kitty
+		/// Bids are accepted until `end_block` and the highest bid at least `reserve` wins
+		pub fn start_english_auction(origin, kitty_id: T::KittyIndex, reserve: MarketBalanceOf<T>, end_block: T::BlockNumber) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!Self::in_auction(kitty_id), Error::InAuction.into());
+			ensure!(Self::kitty_price(kitty_id).is_none(), Error::KittyListed.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+			ensure!(end_block > <system::Module<T>>::block_number(), Error::InvalidAuctionDuration.into());
+
+			<EnglishAuctions<T>>::insert(kitty_id, EnglishAuction { reserve, end_block });
+			<AuctionsEndingAt<T>>::mutate(end_block, |kitties| kitties.push(kitty_id));
+
+			Self::deposit_event(RawEvent::EnglishAuctionStarted(sender, kitty_id, reserve, end_block));
+		}
+
+		/// Place a bid in English auction
+		/// The bid amount is reserved and the previous highest bid is refunded
+		pub fn place_bid(origin, kitty_id: T::KittyIndex, amount: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let auction = Self::english_auction(kitty_id);
+			ensure!(auction.is_some(), Error::NotInAuction.into());
+			let auction = auction.unwrap();
+
+			ensure!(<system::Module<T>>::block_number() < auction.end_block, Error::AuctionEnded.into());
+			ensure!(Self::kitty_owner(kitty_id) != Some(sender.clone()), Error::OwnerCannotBid.into());
+			ensure!(amount >= auction.reserve, Error::PriceTooLow.into());
+
+			let highest_bid = Self::highest_bid(kitty_id);
+			if let Some((_, ref highest_amount)) = highest_bid {
+				ensure!(amount > *highest_amount, Error::PriceTooLow.into());
+			}
+
+			Self::reserve_bid(&sender, amount)?;
+
+			if let Some((bidder, highest_amount)) = highest_bid {
+				T::MarketCurrency::unreserve(&bidder, highest_amount);
+			}
+
+			<HighestBid<T>>::insert(kitty_id, (sender.clone(), amount));
+
+			Self::deposit_event(RawEvent::Bid(sender, kitty_id, amount));
+		}
+
+		/// Make an offer for a kitty
+		/// The amount is reserved until the offer is accepted or withdrawn
+		pub fn make_offer(origin, kitty_id: T::KittyIndex, amount: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let owner = Self::kitty_owner(kitty_id);
+			ensure!(owner.is_some(), Error::InvalidKitty.into());
+			ensure!(owner.unwrap() != sender, Error::OwnerCannotBid.into());
+
+			let key = (kitty_id, sender.clone());
+			let previous = Self::offers(&key);
+
+			Self::reserve_bid(&sender, amount)?;
+
+			if let Some(previous) = previous {
+				T::MarketCurrency::unreserve(&sender, previous);
+			} else {
+				<KittyOfferers<T>>::mutate(kitty_id, |offerers| offerers.push(sender.clone()));
+			}
+
+			<KittyOffers<T>>::insert(&key, amount);
+
+			Self::deposit_event(RawEvent::OfferMade(sender, kitty_id, amount));
+		}
+
+		/// Accept an offer for a kitty
+		pub fn accept_offer(origin, kitty_id: T::KittyIndex, buyer: T::AccountId) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+
+			let key = (kitty_id, buyer.clone());
+			let amount = Self::offers(&key);
+			ensure!(amount.is_some(), Error::OfferNotFound.into());
+			let amount = amount.unwrap();
+
+			Self::ensure_can_pay(&buyer, &sender, kitty_id, amount, true)?;
+			Self::pay_for_kitty(&buyer, &sender, kitty_id, amount, true)?;
+
+			<KittyOffers<T>>::remove(&key);
+			<KittyOfferers<T>>::mutate(kitty_id, |offerers| offerers.retain(|offerer| *offerer != buyer));
+
+			// Other offers are refunded
+			Self::do_transfer(&sender, &buyer, kitty_id)?;
+
+			Self::deposit_event(RawEvent::Sold(sender, buyer, kitty_id, amount, amount));
+		}
+
+		/// Withdraw an offer and unreserve the offered amount
+		pub fn withdraw_offer(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let amount = <KittyOffers<T>>::take(&(kitty_id, sender.clone()));
+			ensure!(amount.is_some(), Error::OfferNotFound.into());
+			<KittyOfferers<T>>::mutate(kitty_id, |offerers| offerers.retain(|offerer| *offerer != sender));
+
+			T::MarketCurrency::unreserve(&sender, amount.unwrap());
+
+			Self::deposit_event(RawEvent::OfferWithdrawn(sender, kitty_id));
+		}
+
+		/// Set a price for others to breed with a kitty
+		/// None to withdraw the siring offer
+		pub fn offer_sire(origin, kitty_id: T::KittyIndex, price: Option<MarketBalanceOf<T>>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+
+			if let Some(ref price) = price {
+				<SireRights<T>>::insert(kitty_id, price);
+			} else {
+				<SireRights<T>>::remove(kitty_id);
+			}
+
+			Self::deposit_event(RawEvent::SireOffered(sender, kitty_id, price));
+		}
+
+		/// Breed own kitty with a kitty offered for siring, with max price willing to pay
+		/// The child is owned by the sender and both parents stay with their owners
+		pub fn breed_with_sire(origin, kitty_id: T::KittyIndex, sire_id: T::KittyIndex, max_price: MarketBalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+			ensure!(Self::can_mint(&sender), Error::NotMinter.into());
+
+			for new_kitty_id in Self::do_breed_with_sire(&sender, kitty_id, sire_id, max_price)? {
+				Self::deposit_event(RawEvent::Bred(sender.clone(), kitty_id, sire_id, new_kitty_id));
+			}
+		}
+
+		/// Approve an account to transfer a kitty
+		pub fn approve(origin, to: T::AccountId, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let owner = Self::kitty_owner(kitty_id);
+			ensure!(owner.is_some(), Error::InvalidKitty.into());
+			let owner = owner.unwrap();
+
+			ensure!(owner == sender || Self::is_operator(&(owner.clone(), sender.clone())), Error::NotApproved.into());
+			Self::ensure_can_approve(&owner, &sender)?;
+
+			<KittyApprovals<T>>::insert(kitty_id, to.clone());
+
+			Self::deposit_event(RawEvent::Approval(owner, to, kitty_id));
+		}
+
+		/// Set the metadata URI of a kitty
+		pub fn set_metadata(origin, kitty_id: T::KittyIndex, uri: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(uri.len() <= MAX_METADATA_LENGTH, Error::MetadataTooLong.into());
+
+			<KittyMetadata<T>>::insert(kitty_id, &uri);
+
+			Self::deposit_event(RawEvent::MetadataSet(sender, kitty_id, uri));
+		}
+
+		/// Clear the metadata URI of a kitty
+		pub fn clear_metadata(origin, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+
+			<KittyMetadata<T>>::remove(kitty_id);
+
+			Self::deposit_event(RawEvent::MetadataCleared(sender, kitty_id));
+		}
+
+		/// Name a kitty. The previous name of the kitty can be used by other kitties.
+		pub fn set_name(origin, kitty_id: T::KittyIndex, name: Vec<u8>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(!name.is_empty(), Error::EmptyName.into());
+			ensure!(name.len() <= MAX_NAME_LENGTH, Error::NameTooLong.into());
+			if let Some(owner) = Self::name_owner(&name) {
+				ensure!(owner == kitty_id, Error::NameTaken.into());
+			}
+
+			if let Some(old_name) = <KittyNames<T>>::take(kitty_id) {
+				<NameOwner<T>>::remove(old_name);
+			}
+			<KittyNames<T>>::insert(kitty_id, &name);
+			<NameOwner<T>>::insert(&name, kitty_id);
+
+			Self::deposit_event(RawEvent::NameSet(sender, kitty_id, name));
+		}
+
+		/// Lock or unlock a kitty. A locked kitty cannot be transferred, sold or bred.
+		pub fn set_lock(origin, kitty_id: T::KittyIndex, locked: bool) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(kitty_id))), Error::NotOwner.into());
+			// Auctions settle without the owner, so they must not be blocked by a lock
+			ensure!(!Self::in_auction(kitty_id), Error::InAuction.into());
+
+			if locked {
+				<KittyLocked<T>>::insert(kitty_id, true);
+			} else {
+				<KittyLocked<T>>::remove(kitty_id);
+			}
+
+			Self::deposit_event(RawEvent::LockSet(sender, kitty_id, locked));
+		}
+
+		/// Approve or disapprove an operator to transfer all kitties of the sender
+		pub fn set_approval_for_all(origin, operator: T::AccountId, approved: bool) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			ensure!(sender != operator, Error::ApproveSelf.into());
+			Self::ensure_can_approve(&sender, &sender)?;
+
+			let key = (sender.clone(), operator.clone());
+			if approved {
+				<OperatorApprovals<T>>::insert(&key, true);
+			} else {
+				<OperatorApprovals<T>>::remove(&key);
+			}
+
+			Self::deposit_event(RawEvent::ApprovalForAll(sender, operator, approved));
+		}
+
+		/// Transfer a kitty on behalf of its owner
+		/// The sender must be the owner, the approved account or an operator of the owner
+		pub fn transfer_from(origin, from: T::AccountId, to: T::AccountId, kitty_id: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			ensure!(!Self::is_frozen(&sender), Error::AccountFrozen.into());
+
+			ensure!(<OwnedKitties<T>>::exists(&(from.clone(), Some(kitty_id))), Error::NotOwner.into());
+			ensure!(Self::is_approved_or_owner(&sender, &from, kitty_id), Error::NotApproved.into());
+			ensure!(!<EnglishAuctions<T>>::exists(kitty_id), Error::InAuction.into());
+			ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+
+			Self::do_transfer(&from, &to, kitty_id)?;
+
+			Self::deposit_event(RawEvent::Transferred(from, to, kitty_id));
+		}
+
+		/// Propose to swap own kitty with the kitty of another owner
+		pub fn propose_swap(origin, my_kitty: T::KittyIndex, their_kitty: T::KittyIndex) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+			Self::ensure_not_frozen(&sender)?;
+
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(my_kitty))), Error::NotOwner.into());
+			let target = Self::kitty_owner(their_kitty);
+			ensure!(target.is_some(), Error::InvalidKitty.into());
+			ensure!(target.unwrap() != sender, Error::TransferToSelf.into());
+
+			let proposal_id = Self::next_swap_id();
+			<NextSwapId<T>>::put(proposal_id.wrapping_add(1));
+
+			<SwapProposals<T>>::insert(proposal_id, SwapProposal {
+				proposer: sender.clone(),
+				proposer_kitty: my_kitty,
+				target_kitty: their_kitty,
+			});
+			<KittySwapProposals<T>>::mutate(my_kitty, |ids| ids.push(proposal_id));
+			<KittySwapProposals<T>>::mutate(their_kitty, |ids| ids.push(proposal_id));
+
+			Self::deposit_event(RawEvent::SwapProposed(sender, proposal_id, my_kitty, their_kitty));
+		}
+
+		/// Cancel a swap proposal made by the sender
+		pub fn cancel_swap(origin, proposal_id: u64) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let proposal = Self::swap_proposal(proposal_id);
+			ensure!(proposal.is_some(), Error::SwapNotFound.into());
+			ensure!(proposal.unwrap().proposer == sender, Error::NotOwner.into());
+
+			Self::remove_swap_proposal(proposal_id);
+
+			Self::deposit_event(RawEvent::SwapCancelled(sender, proposal_id));
+		}
+
+		/// Accept a swap proposal for a kitty owned by the sender
+		pub fn accept_swap(origin, proposal_id: u64) {
+			let sender = ensure_signed(origin)?;
+			ensure!(!Self::paused(), Error::Paused.into());
+
+			let proposal = Self::swap_proposal(proposal_id);
+			ensure!(proposal.is_some(), Error::SwapNotFound.into());
+			let proposal = proposal.unwrap();
+
+			// Both parties must still own their kitties
+			ensure!(<OwnedKitties<T>>::exists(&(sender.clone(), Some(proposal.target_kitty))), Error::NotOwner.into());
+			ensure!(<OwnedKitties<T>>::exists(&(proposal.proposer.clone(), Some(proposal.proposer_kitty))), Error::NotOwner.into());
+			ensure!(!<EnglishAuctions<T>>::exists(proposal.proposer_kitty), Error::InAuction.into());
+			ensure!(!<EnglishAuctions<T>>::exists(proposal.target_kitty), Error::InAuction.into());
+			ensure!(!Self::is_locked(proposal.proposer_kitty), Error::KittyLocked.into());
+			ensure!(!Self::is_locked(proposal.target_kitty), Error::KittyLocked.into());
+
+			Self::do_transfer(&proposal.proposer, &sender, proposal.proposer_kitty)?;
+			Self::do_transfer(&sender, &proposal.proposer, proposal.target_kitty)?;
+
+			Self::remove_swap_proposal(proposal_id);
+
+			Self::deposit_event(RawEvent::Swapped(proposal.proposer, sender, proposal.proposer_kitty, proposal.target_kitty));
+		}
+	}
+}
+
+/// Combine a DNA byte of both parents. Each set bit of the selector picks the bit of `dna1`,
+/// each unset bit picks the bit of `dna2`.
+pub fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
+	((selector & dna1) | (!selector & dna2))
+}
+
+/// Generation of a child, one more than the older generation of the parents
+fn child_generation<BlockNumber>(kitty1: &Kitty<BlockNumber>, kitty2: &Kitty<BlockNumber>) -> u32 {
+	kitty1.generation.max(kitty2.generation).saturating_add(1)
+}
+
+/// Combine DNA of both parents, one byte of the selector for each byte of the child DNA.
+/// Missing bytes of parents with shorter DNA are treated as zero.
+pub fn combine_parents_dna(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8> {
+	selector.iter().enumerate().map(|(i, selector)| {
+		let byte1 = dna1.get(i).cloned().unwrap_or(0);
+		let byte2 = dna2.get(i).cloned().unwrap_or(0);
+		combine_dna(byte1, byte2, *selector)
+	}).collect()
+}
+
+/// Bias a random selector byte toward the rarer parent.
+/// Each bit is combined with the neighbouring bit, so 3 out of 4 bits pick the rarer parent on average.
+pub fn weight_selector(selector: u8, rarity1: u32, rarity2: u32) -> u8 {
+	if rarity1 > rarity2 {
+		selector | selector.rotate_left(1)
+	} else if rarity1 < rarity2 {
+		selector & selector.rotate_left(1)
+	} else {
+		selector
+	}
+}
+
+/// Rarity score of a DNA. Higher is rarer.
+/// The high nibble of each byte is weighted double of the low nibble.
+pub fn dna_rarity(dna: &[u8]) -> u32 {
+	dna.iter().map(|byte| (*byte >> 4) as u32 * 2 + (*byte & 0x0f) as u32).sum()
+}
+
+/// Gender of a kitty is determined by the lowest bit of its DNA
+pub fn dna_gender(dna: &[u8]) -> KittyGender {
+	match dna.first() {
+		Some(byte) if byte % 2 == 1 => KittyGender::Female,
+		_ => KittyGender::Male,
+	}
+}
+
+/// Fur color is the second byte of DNA
+pub fn dna_fur_color(dna: &[u8]) -> u8 {
+	dna.get(1).cloned().unwrap_or(0)
+}
+
+/// Eye shape is the high nibble of the third byte of DNA
+pub fn dna_eye_shape(dna: &[u8]) -> u8 {
+	dna.get(2).map(|byte| byte >> 4).unwrap_or(0)
+}
+
+/// Pattern is the low nibble of the third byte of DNA
+pub fn dna_pattern(dna: &[u8]) -> u8 {
+	dna.get(2).map(|byte| byte & 0x0f).unwrap_or(0)
+}
+
+/// Key of `ChildrenOf`, the same for both orders of the parents
+fn parent_pair<KittyIndex: Ord>(kitty_id_1: KittyIndex, kitty_id_2: KittyIndex) -> (KittyIndex, KittyIndex) {
+	if kitty_id_1 <= kitty_id_2 {
+		(kitty_id_1, kitty_id_2)
+	} else {
+		(kitty_id_2, kitty_id_1)
+	}
+}
+
+/// Bias each byte of a selector toward the parent with the rarer DNA, see `weight_selector`
+fn weight_parents_selector(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8> {
+	let rarity1 = dna_rarity(dna1);
+	let rarity2 = dna_rarity(dna2);
+	selector.iter().map(|byte| weight_selector(*byte, rarity1, rarity2)).collect()
+}
+
+fn mutate_dna(dna: u8, mask: u8, selector: u8, mutation_rate: u8) -> u8 {
+	if selector < mutation_rate {
+		dna ^ mask
+	} else {
+		dna
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Current price of a kitty in Dutch auction. None means not in auction.
+	pub fn auction_price(kitty_id: T::KittyIndex) -> Option<MarketBalanceOf<T>> {
+		Self::auction(kitty_id).map(|auction| {
+			let now = <system::Module<T>>::block_number();
+			if now >= auction.end_block {
+				return auction.end_price;
+			}
+
+			// Linear interpolation between start price and end price.
+			// `diff * elapsed / duration` is split on `duration` so it cannot overflow for any price.
+			let elapsed: u64 = (now - auction.start_block).as_();
+			let duration: u64 = (auction.end_block - auction.start_block).as_();
+			let diff = auction.start_price - auction.end_price;
+			let sa = <MarketBalanceOf<T> as As<u64>>::sa;
+			let whole = diff / sa(duration) * sa(elapsed);
+			let rest: u64 = (diff % sa(duration)).as_();
+			let part = (rest as u128 * elapsed as u128 / duration as u128) as u64;
+			auction.start_price - whole - sa(part)
+		})
+	}
+
+	/// Whether `who` can create or breed kitties
+	pub fn can_mint(who: &T::AccountId) -> bool {
+		!Self::minting_restricted() || Self::is_minter(who)
+	}
+
+	/// Whether `who` can transfer a kitty owned by `owner`
+	pub fn is_approved_or_owner(who: &T::AccountId, owner: &T::AccountId, kitty_id: T::KittyIndex) -> bool {
+		who == owner ||
+			Self::approved(kitty_id).map(|approved| approved == *who).unwrap_or(false) ||
+			Self::is_operator(&(owner.clone(), who.clone()))
+	}
+
+	/// DNA of a child of two kitties bred with the given selector, before random mutations.
+	/// Does not modify storage. None if any of the kitties does not exist.
+	pub fn preview_breed(kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex, selector: Vec<u8>) -> Option<Vec<u8>> {
+		let kitty1 = Self::kitty(kitty_id_1)?;
+		let kitty2 = Self::kitty(kitty_id_2)?;
+		Some(Self::combine_parents(&kitty1.dna, &kitty2.dna, &selector))
+	}
+
+	/// Whether a kitty exists, without decoding it
+	pub fn kitty_exists(kitty_id: T::KittyIndex) -> bool {
+		<Kitties<T>>::exists(kitty_id)
+	}
+
+	/// Rarity score of a kitty. None means the kitty does not exist.
+	pub fn rarity(kitty_id: T::KittyIndex) -> Option<u32> {
+		Self::kitty(kitty_id).map(|kitty| dna_rarity(&kitty.dna))
+	}
+
+	/// Fur color of a kitty. None means the kitty does not exist.
+	pub fn fur_color(kitty_id: T::KittyIndex) -> Option<u8> {
+		Self::kitty(kitty_id).map(|kitty| dna_fur_color(&kitty.dna))
+	}
+
+	/// Eye shape of a kitty. None means the kitty does not exist.
+	pub fn eye_shape(kitty_id: T::KittyIndex) -> Option<u8> {
+		Self::kitty(kitty_id).map(|kitty| dna_eye_shape(&kitty.dna))
+	}
+
+	/// Pattern of a kitty. None means the kitty does not exist.
+	pub fn pattern(kitty_id: T::KittyIndex) -> Option<u8> {
+		Self::kitty(kitty_id).map(|kitty| dna_pattern(&kitty.dna))
+	}
+
+	/// Block number at which a kitty is created. None means the kitty does not exist.
+	/// Kept in `Kitty::birth` rather than a separate map, so it is read with the kitty.
+	pub fn birth_block(kitty_id: T::KittyIndex) -> Option<T::BlockNumber> {
+		Self::kitty(kitty_id).map(|kitty| kitty.birth)
+	}
+
+	/// Number of blocks since a kitty is created. None means the kitty does not exist.
+	pub fn age(kitty_id: T::KittyIndex) -> Option<T::BlockNumber> {
+		Self::birth_block(kitty_id).map(|birth| <system::Module<T>>::block_number() - birth)
+	}
+
+	/// Full details of a kitty in a single query
+	pub fn kitty_details(kitty_id: T::KittyIndex) -> Option<KittyDetails<T::AccountId, MarketBalanceOf<T>>> {
+		let kitty = Self::kitty(kitty_id)?;
+		let owner = Self::kitty_owner(kitty_id)?;
+		Some(KittyDetails {
+			gender: dna_gender(&kitty.dna),
+			dna: kitty.dna,
+			owner,
+			price: Self::kitty_price(kitty_id),
+			generation: kitty.generation,
+		})
+	}
+
+	/// All kitties owned by an account
+	pub fn owned_kitties_iter(owner: &T::AccountId) -> Vec<T::KittyIndex> {
+		<OwnedKittiesList<T>>::items(owner)
+	}
+
+	/// At most `limit` kitties owned by an account, following kitty `start`.
+	/// The last returned kitty is the `start` of the next page.
+	pub fn kitties_of_paged(owner: &T::AccountId, start: Option<T::KittyIndex>, limit: u32) -> Vec<T::KittyIndex> {
+		<OwnedKittiesList<T>>::page(owner, start, limit)
+	}
+
+	/// Owners of the kitties, in the same order. None for kitties that do not exist.
+	pub fn owners_of(kitty_ids: Vec<T::KittyIndex>) -> Vec<Option<T::AccountId>> {
+		kitty_ids.into_iter().map(Self::kitty_owner).collect()
+	}
+
+	/// Existing kitties with ids from `start` up to `start + limit` and their owners.
+	/// Ids of kitties that no longer exist are skipped.
+	pub fn all_kitties(start: T::KittyIndex, limit: u32) -> Vec<(T::KittyIndex, T::AccountId)> {
+		let count = Self::kitties_count();
+		let mut kitties = Vec::new();
+		let mut kitty_id = start;
+		let mut scanned = 0;
+		while kitty_id < count && scanned < limit {
+			if let Some(owner) = Self::kitty_owner(kitty_id) {
+				kitties.push((kitty_id, owner));
+			}
+			kitty_id = kitty_id + One::one();
+			scanned += 1;
+		}
+		kitties
+	}
+
+	/// Owner and price of a kitty for sale. None if the kitty is not for sale.
+	pub fn sale_info(kitty_id: T::KittyIndex) -> Option<(T::AccountId, MarketBalanceOf<T>)> {
+		let price = Self::kitty_price(kitty_id)?;
+		let owner = Self::kitty_owner(kitty_id)?;
+		Some((owner, price))
+	}
+
+	/// All kitties currently for sale with their prices
+	pub fn listings() -> Vec<(T::KittyIndex, MarketBalanceOf<T>)> {
+		Self::for_sale().into_iter()
+			.filter_map(|kitty_id| Self::kitty_price(kitty_id).map(|price| (kitty_id, price)))
+			.collect()
+	}
+
+	fn in_auction(kitty_id: T::KittyIndex) -> bool {
+		<KittyAuctions<T>>::exists(kitty_id) || <EnglishAuctions<T>>::exists(kitty_id)
+	}
+
+	/// Settle auctions ended at block `now`.
+	/// Auctions exceeding the per block limit are deferred to the next block.
+	fn settle_english_auctions(now: T::BlockNumber) {
+		let mut ended = <AuctionsEndingAt<T>>::take(now);
+		let limit = T::MaxAuctionsSettledPerBlock::get() as usize;
+		if ended.len() > limit {
+			let deferred = ended.split_off(limit);
+			<AuctionsEndingAt<T>>::mutate(now + One::one(), |kitties| kitties.extend(deferred));
+		}
+
+		for kitty_id in ended {
+			if <EnglishAuctions<T>>::exists(kitty_id) {
+				Self::settle_english_auction(kitty_id);
+			}
+		}
+	}
+
+	/// Migrate storage from an older version to `STORAGE_VERSION`.
+	/// At most `MAX_MIGRATED_KITTIES_PER_BLOCK` kitties are migrated per block, the module is paused until done.
+	fn migrate() {
+		let (start, end, was_paused) = match Self::migration_cursor() {
+			Some(cursor) => cursor,
+			None => {
+				let was_paused = Self::paused();
+				<Paused<T>>::put(true);
+				(0, Self::kitties_count().as_(), was_paused)
+			},
+		};
+
+		let next = end.min(start.saturating_add(MAX_MIGRATED_KITTIES_PER_BLOCK));
+		if Self::storage_version() < 1 {
+			for i in start..next {
+				Self::migrate_kitty_to_struct(<T::KittyIndex as As<u64>>::sa(i));
+			}
+		}
+
+		if next < end {
+			<MigrationCursor<T>>::put((next, end, was_paused));
+			return;
+		}
+		<MigrationCursor<T>>::kill();
+		<Paused<T>>::put(was_paused);
+		<StorageVersion<T>>::put(STORAGE_VERSION);
+	}
+
+	/// Rewrite a kitty stored as `Kitty([u8; 16])` into the current layout.
+	/// Generation and birth are defaulted to zero and breeding charges are granted.
+	/// The owner is counted in the owned kitties count and recorded as breeder where unknown.
+	fn migrate_kitty_to_struct(kitty_id: T::KittyIndex) {
+		let raw = support::storage::get_raw(&<Kitties<T>>::key_for(kitty_id));
+		// The old layout is exactly the 16 bytes of DNA
+		let dna = match raw.filter(|raw| raw.len() == 16) {
+			Some(dna) => dna,
+			None => return,
+		};
+
+		<Kitties<T>>::insert(kitty_id, Kitty {
+			dna,
+			generation: 0,
+			birth: Zero::zero(),
+		});
+		<KittyCharges<T>>::insert(kitty_id, T::BreedingCharges::get());
+
+		if let Some(owner) = Self::kitty_owner(kitty_id) {
+			if !<KittyOriginalBreeder<T>>::exists(kitty_id) {
+				<KittyOriginalBreeder<T>>::insert(kitty_id, &owner);
+			}
+			// The old layout has no counts, and the owned kitties list is only read up to its count
+			<OwnedKittiesCount<T>>::mutate(&owner, |count| *count = count.saturating_add(1));
+		}
+	}
+
+	/// Remove listings expired at block `n`.
+	/// Listings exceeding the per block limit are carried over to the next block.
+	fn remove_expired_listings(n: T::BlockNumber) {
+		if n.is_zero() {
+			return;
+		}
+		let expired_at = n - One::one();
+		let mut kitties = <ListingsExpiringAt<T>>::take(expired_at);
+		if kitties.len() > MAX_EXPIRED_LISTINGS_PER_BLOCK {
+			let remaining = kitties.split_off(MAX_EXPIRED_LISTINGS_PER_BLOCK);
+			<ListingsExpiringAt<T>>::mutate(n, |kitties| kitties.extend(remaining));
+		}
+
+		for kitty_id in kitties {
+			// The kitty may have been delisted or relisted with another expiry
+			let expired = Self::listing_expiry(kitty_id).map_or(false, |expires_at| expires_at < n);
+			if !expired {
+				continue;
+			}
+			if let Some(owner) = Self::kitty_owner(kitty_id) {
+				Self::remove_listing(&owner, kitty_id);
+				Self::deposit_event(RawEvent::Delisted(owner, kitty_id));
+			}
+		}
+	}
+
+	fn settle_english_auction(kitty_id: T::KittyIndex) {
+		<EnglishAuctions<T>>::remove(kitty_id);
+
+		let owner = match Self::kitty_owner(kitty_id) {
+			Some(owner) => owner,
+			None => return,
+		};
+
+		if let Some((bidder, amount)) = <HighestBid<T>>::take(kitty_id) {
+			// The bid is reserved so this only fails if a recipient cannot receive its payment
+			if Self::ensure_can_pay(&bidder, &owner, kitty_id, amount, true).is_ok() &&
+				Self::pay_for_kitty(&bidder, &owner, kitty_id, amount, true).is_ok()
+			{
+				Self::move_kitty(&owner, &bidder, kitty_id);
+				Self::deposit_event(RawEvent::Sold(owner, bidder, kitty_id, amount, amount));
+				return;
+			}
+			T::MarketCurrency::unreserve(&bidder, amount);
+		}
+
+		Self::deposit_event(RawEvent::EnglishAuctionEnded(owner, kitty_id));
+	}
+
+	/// Random value of `Trait::DnaLength` bytes
+	fn random_value(sender: &T::AccountId) -> Vec<u8> {
+		Self::random_value_with_seed(sender, &[])
+	}
+
+	fn random_value_with_seed(sender: &T::AccountId, seed: &[u8]) -> Vec<u8> {
+		let nonce = Self::nonce();
+		<Nonce<T>>::put(nonce.wrapping_add(1));
+
+		let payload = (<system::Module<T>>::random_seed(), sender, <system::Module<T>>::extrinsic_index(), <system::Module<T>>::block_number(), nonce, seed);
+
+		// Hash the payload with a counter until enough bytes are generated
+		let length = T::DnaLength::get() as usize;
+		let mut value = Vec::with_capacity(length);
+		let mut chunk: u32 = 0;
+		while value.len() < length {
+			value.extend_from_slice(&(&payload, chunk).using_encoded(blake2_128));
+			chunk += 1;
+		}
+		value.truncate(length);
+		value
+	}
+
+	/// Split a sale price into (royalty, remaining)
+	fn royalty_split(price: MarketBalanceOf<T>) -> (MarketBalanceOf<T>, MarketBalanceOf<T>) {
+		// `Perbill` never overflows, even at the max price
+		let royalty = Perbill::from_percent(T::RoyaltyPercent::get().min(100)) * price;
+		// Rounding goes to the seller so both parts always sum to the price
+		(royalty, price.saturating_sub(royalty))
+	}
+
+	/// Buy a kitty already checked by `can_buy`
+	fn do_buy(buyer: &T::AccountId, kitty_id: T::KittyIndex, max_price: MarketBalanceOf<T>) -> result::Result<(), &'static str> {
+		let owner = Self::kitty_owner(kitty_id).ok_or(Error::InvalidKitty)?;
+		let kitty_price = Self::kitty_price(kitty_id).ok_or(Error::NotForSale)?;
+
+		Self::pay_for_kitty(buyer, &owner, kitty_id, kitty_price, false)?;
+
+		Self::do_transfer(&owner, buyer, kitty_id)?;
+
+		Self::deposit_event(RawEvent::Sold(owner, buyer.clone(), kitty_id, kitty_price, max_price));
+
+		Ok(())
+	}
+
+	/// Marketplace fee of a sale price. Never more than the price.
+	fn marketplace_fee(price: MarketBalanceOf<T>) -> MarketBalanceOf<T> {
+		Perbill::from_percent(T::MarketplaceFeePercent::get().min(100)) * price
+	}
+
+	/// Split the price of a kitty into payments to the fee collector, the breeder and the seller
+	fn sale_payments(seller: &T::AccountId, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>) -> Vec<(T::AccountId, MarketBalanceOf<T>)> {
+		// The fee and royalty are taken out of the price so the buyer never pays more than the price
+		let fee = Self::marketplace_fee(price);
+		let mut remaining = price.saturating_sub(fee);
+		let mut payments = vec![(T::FeeCollector::get(), fee)];
+
+		let breeder = Self::breeder(kitty_id);
+		if breeder != *seller {
+			let (royalty, _) = Self::royalty_split(price);
+			let royalty = royalty.min(remaining);
+			payments.push((breeder, royalty));
+			remaining = remaining.saturating_sub(royalty);
+		}
+
+		payments.push((seller.clone(), remaining));
+		payments.retain(|(_, amount)| !amount.is_zero());
+		payments
+	}
+
+	/// Check every payment of a sale up front so the sale never fails half way.
+	/// The buyer pays exactly the price, no transfer or creation fee is charged on top of it.
+	/// `reserved` pays from funds the buyer reserved for an offer or bid.
+	fn ensure_can_pay(buyer: &T::AccountId, seller: &T::AccountId, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>, reserved: bool) -> result::Result<(), &'static str> {
+		Self::ensure_not_frozen(buyer)?;
+		Self::ensure_not_frozen(seller)?;
+
+		if reserved {
+			ensure!(T::MarketCurrency::reserved_balance(buyer) >= price, Error::InsufficientBalance.into());
+			return Self::ensure_can_receive(seller, kitty_id, price);
+		}
+
+		let free_balance = T::MarketCurrency::free_balance(buyer);
+		ensure!(free_balance >= price, Error::InsufficientBalance.into());
+		let new_balance = free_balance - price;
+		ensure!(new_balance >= T::MarketCurrency::minimum_balance(), Error::WouldKillAccount.into());
+		T::MarketCurrency::ensure_can_withdraw(buyer, price, WithdrawReason::Transfer, new_balance)
+			.map_err(|_| Error::InsufficientBalance)?;
+
+		Self::ensure_can_receive(seller, kitty_id, price)
+	}
+
+	/// Check every recipient of a sale can receive its payment
+	fn ensure_can_receive(seller: &T::AccountId, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>) -> result::Result<(), &'static str> {
+		let minimum_balance = T::MarketCurrency::minimum_balance();
+		for (dest, amount) in Self::sale_payments(seller, kitty_id, price).iter() {
+			if T::MarketCurrency::total_balance(dest).is_zero() {
+				ensure!(*amount >= minimum_balance, Error::PaymentBelowMinimum.into());
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Pay for a kitty, checked by `ensure_can_pay`. Every sale is paid from here.
+	/// The price is taken once and split between the recipients, so either everyone is paid or nobody is.
+	fn pay_for_kitty(buyer: &T::AccountId, seller: &T::AccountId, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>, reserved: bool) -> result::Result<(), &'static str> {
+		let funds = if reserved {
+			// The reserved balance is checked to cover the price
+			let (funds, _) = T::MarketCurrency::slash_reserved(buyer, price);
+			funds
+		} else {
+			T::MarketCurrency::withdraw(buyer, price, WithdrawReason::Transfer, ExistenceRequirement::KeepAlive)
+				.map_err(|_| Error::InsufficientBalance)?
+		};
+		Self::settle_sale(funds, seller, kitty_id, price);
+
+		Ok(())
+	}
+
+	/// Split the funds of a sale between the recipients, checked by `ensure_can_receive`
+	fn settle_sale(funds: MarketNegativeImbalanceOf<T>, seller: &T::AccountId, kitty_id: T::KittyIndex, price: MarketBalanceOf<T>) {
+		let mut funds = funds;
+		for (dest, amount) in Self::sale_payments(seller, kitty_id, price) {
+			let (payment, remaining) = funds.split(amount);
+			T::MarketCurrency::resolve_creating(&dest, payment);
+			funds = remaining;
+		}
+		// The payments add up to the price so nothing is left
+		T::MarketCurrency::resolve_creating(seller, funds);
+	}
+
+	fn ensure_not_frozen(who: &T::AccountId) -> result::Result<(), &'static str> {
+		ensure!(!Self::is_frozen(who), Error::AccountFrozen.into());
+		Ok(())
+	}
+
+	/// Reserve the amount of a bid or offer
+	fn reserve_bid(who: &T::AccountId, amount: MarketBalanceOf<T>) -> result::Result<(), &'static str> {
+		Self::ensure_not_frozen(who)?;
+		T::MarketCurrency::reserve(who, amount)
+	}
+
+	/// Neither the owner nor the account granting an approval on its behalf can be frozen
+	fn ensure_can_approve(owner: &T::AccountId, sender: &T::AccountId) -> result::Result<(), &'static str> {
+		Self::ensure_not_frozen(owner)?;
+		Self::ensure_not_frozen(sender)
+	}
+
+	fn charge_creation_fee(who: &T::AccountId) -> result::Result<(), &'static str> {
+		Self::charge_fee(who, T::CreationFee::get())
+	}
+
+	fn charge_fee(who: &T::AccountId, fee: BalanceOf<T>) -> result::Result<(), &'static str> {
+		let imbalance = Self::withdraw_fee(who, fee)?;
+		T::OnFeePaid::on_unbalanced(imbalance);
+
+		Ok(())
+	}
+
+	/// Withdraw a fee, to be handled by `OnFeePaid` once the call cannot fail anymore
+	fn withdraw_fee(who: &T::AccountId, fee: BalanceOf<T>) -> result::Result<NegativeImbalanceOf<T>, &'static str> {
+		if fee.is_zero() {
+			return Ok(NegativeImbalanceOf::<T>::zero());
+		}
+
+		T::Currency::withdraw(who, fee, WithdrawReason::Fee, ExistenceRequirement::KeepAlive)
+			.map_err(|_| Error::InsufficientBalance.into())
+	}
+
+	/// Make sure `count` kitties can be created, reusing free ids first if enabled.
+	fn ensure_can_create(count: u32) -> result::Result<(), &'static str> {
+		let reused = if T::RecycleIds::get() {
+			Self::free_ids().len().min(count as usize) as u32
+		} else {
+			0
+		};
+		let new_ids = count - reused;
+		if new_ids == 0 {
+			return Ok(());
+		}
+
+		let kitties_count = Self::kitties_count();
+		// Check overflow first so a full index space is reported as such whatever the supply cap
+		kitties_count.checked_add(&<T::KittyIndex as As<u64>>::sa(new_ids as u64)).ok_or(Error::CountOverflow)?;
+		let total = kitties_count.as_() + new_ids as u64;
+		ensure!(total <= T::MaxKitties::get() as u64, Error::MaxSupplyReached.into());
+
+		Ok(())
+	}
+
+	/// The id of the next kitty to create. This does not allocate the id.
+	fn next_kitty_id() -> result::Result<T::KittyIndex, &'static str> {
+		Self::ensure_can_create(1)?;
+
+		if T::RecycleIds::get() {
+			if let Some(kitty_id) = Self::free_ids().last() {
+				return Ok(*kitty_id);
+			}
+		}
+
+		Ok(Self::kitties_count())
+	}
+
+	fn insert_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+		<OwnedKittiesList<T>>::append(owner, kitty_id);
+
+		Self::deposit_event(RawEvent::CollectionChanged(owner.clone()));
+	}
+
+	/// Allocate an id and store a new kitty. All kitties must be created from here.
+	fn insert_kitty(owner: &T::AccountId, dna: Vec<u8>, generation: u32) -> result::Result<T::KittyIndex, &'static str> {
+		let kitty_id = Self::next_kitty_id()?;
+
+		// Create and store kitty
+		let kitty = Kitty {
+			dna,
+			generation,
+			birth: <system::Module<T>>::block_number(),
+		};
+		<Kitties<T>>::insert(kitty_id, kitty);
+		if kitty_id < Self::kitties_count() {
+			// Reused the id of a destroyed kitty
+			<FreeIds<T>>::mutate(|ids| ids.pop());
+		} else {
+			// `next_kitty_id` ensures this does not overflow
+			<KittiesCount<T>>::put(kitty_id + One::one());
+		}
+		<KittyOriginalBreeder<T>>::insert(kitty_id, owner.clone());
+		<KittyCharges<T>>::insert(kitty_id, T::BreedingCharges::get());
+		<KittyOwners<T>>::insert(kitty_id, owner.clone());
+		<CreatedCount<T>>::mutate(owner, |count| *count = count.saturating_add(1));
+
+		Self::insert_owned_kitty(owner, kitty_id);
+
+		Ok(kitty_id)
+	}
+
+	/// Check a kitty exists, is not locked, has charges left and is off cooldown
+	fn ensure_breedable(kitty_id: T::KittyIndex) -> result::Result<(), &'static str> {
+		ensure!(Self::kitty_exists(kitty_id), Error::InvalidKitty.into());
+		ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+		ensure!(!<KittyPrices<T>>::exists(kitty_id), Error::BreedListed.into());
+		ensure!(Self::charges(kitty_id) > 0, Error::NoChargesLeft.into());
+		ensure!(Self::cooldown_until(kitty_id) <= <system::Module<T>>::block_number(), Error::BreedCooldown.into());
+		Ok(())
+	}
+
+	/// Tell the block number to wait for if breeding failed due to cooldown
+	fn on_breed_failed(kitty_id: T::KittyIndex, error: &'static str) -> &'static str {
+		if error == Error::BreedCooldown.as_str() {
+			// Failed calls are not reverted, so the event is kept
+			Self::deposit_event(RawEvent::BreedFailedCooldown(kitty_id, Self::cooldown_until(kitty_id)));
+		}
+		error
+	}
+
+	fn breeding_pair(kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> result::Result<(KittyOf<T>, KittyOf<T>), &'static str> {
+		Self::ensure_breedable(kitty_id_1).map_err(|e| Self::on_breed_failed(kitty_id_1, e))?;
+		Self::ensure_breedable(kitty_id_2).map_err(|e| Self::on_breed_failed(kitty_id_2, e))?;
+		ensure!(kitty_id_1 != kitty_id_2, Error::SameParents.into());
+
+		// Only decode the kitties once validated
+		let kitty1 = Self::kitty(kitty_id_1).ok_or(Error::InvalidKitty)?;
+		let kitty2 = Self::kitty(kitty_id_2).ok_or(Error::InvalidKitty)?;
+		ensure!(child_generation(&kitty1, &kitty2) <= T::MaxGeneration::get(), Error::MaxGenerationReached.into());
+		let inbreeding_depth = T::InbreedingDepth::get();
+		if inbreeding_depth > 0 {
+			ensure!(!Self::is_related(kitty_id_1, kitty_id_2, inbreeding_depth), Error::KittiesRelated.into());
+		}
+
+		Ok((kitty1, kitty2))
+	}
+
+	/// Whether a kitty can currently be bred by its owner.
+	/// It must exist, not be locked or listed, have charges left and be off cooldown.
+	pub fn can_breed(kitty_id: T::KittyIndex) -> bool {
+		Self::ensure_breedable(kitty_id).is_ok()
+	}
+
+	/// Check whether `buy` of a kitty for at most `max_price` would succeed, without modifying storage.
+	/// Returns the error `buy` would fail with.
+	pub fn can_buy(buyer: &T::AccountId, kitty_id: T::KittyIndex, max_price: MarketBalanceOf<T>) -> result::Result<(), &'static str> {
+		ensure!(!Self::paused(), Error::Paused.into());
+		ensure!(!Self::is_frozen(buyer), Error::AccountFrozen.into());
+
+		let owner = Self::kitty_owner(kitty_id).ok_or(Error::InvalidKitty)?;
+		let kitty_price = Self::kitty_price(kitty_id).ok_or(Error::NotForSale)?;
+		ensure!(owner != *buyer, Error::BuyOwnKitty.into());
+		ensure!(max_price >= kitty_price, Error::PriceTooLow.into());
+		ensure!(!Self::is_locked(kitty_id), Error::KittyLocked.into());
+
+		// Never send funds to an account not actually holding the kitty
+		ensure!(<Kitties<T>>::exists(kitty_id), Error::InvalidKitty.into());
+		ensure!(<OwnedKitties<T>>::exists(&(owner.clone(), Some(kitty_id))), Error::InconsistentOwner.into());
+		// A listing made by an operator is only valid while the operator is approved
+		if let Some(lister) = Self::lister(kitty_id) {
+			ensure!(Self::is_approved_or_owner(&lister, &owner, kitty_id), Error::ListerNotApproved.into());
+		}
+
+		if let Some(expires_at) = Self::listing_expiry(kitty_id) {
+			ensure!(<system::Module<T>>::block_number() <= expires_at, Error::ListingExpired.into());
+		}
+
+		Self::ensure_can_pay(buyer, &owner, kitty_id, kitty_price, false)
+	}
+
+	/// Other kitties bred by the same pair of parents, in either order
+	pub fn siblings(kitty_id: T::KittyIndex) -> Vec<T::KittyIndex> {
+		match Self::parents(kitty_id) {
+			Some((parent_1, parent_2)) => Self::children_of(parent_pair(parent_1, parent_2))
+				.into_iter()
+				.filter(|id| *id != kitty_id)
+				.collect(),
+			None => Vec::new(),
+		}
+	}
+
+	/// Whether two kitties share an ancestor within `max_depth` generations.
+	/// A kitty is its own ancestor at depth zero, so parent and child are related at depth one.
+	pub fn is_related(kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex, max_depth: u32) -> bool {
+		let ancestors = Self::ancestors(kitty_id_1, max_depth);
+		Self::ancestors(kitty_id_2, max_depth).iter().any(|id| ancestors.contains(id))
+	}
+
+	/// The kitty and its known ancestors within `max_depth` generations
+	fn ancestors(kitty_id: T::KittyIndex, max_depth: u32) -> Vec<T::KittyIndex> {
+		let mut ancestors = vec![kitty_id];
+		let mut generation = vec![kitty_id];
+		for _ in 0..max_depth {
+			let mut parents = Vec::new();
+			for id in generation {
+				if let Some((parent1, parent2)) = Self::parents(id) {
+					for parent in [parent1, parent2].iter() {
+						if !ancestors.contains(parent) {
+							ancestors.push(*parent);
+							parents.push(*parent);
+						}
+					}
+				}
+			}
+			if parents.is_empty() {
+				break;
+			}
+			generation = parents;
+		}
+		ancestors
+	}
+
+	/// Fee to breed kitties. `BreedFeeBase * (1 + kitties_count / BreedFeeStep)`, saturating.
+	pub fn current_breed_fee() -> BalanceOf<T> {
+		let step = T::BreedFeeStep::get() as u64;
+		let count: u64 = Self::kitties_count().as_();
+		let multiplier = if step == 0 { 1 } else { 1 + count / step };
+		T::BreedFeeBase::get().saturating_mul(<BalanceOf<T> as As<u64>>::sa(multiplier))
+	}
+
+	/// Breeding cooldown of a kitty. `BaseCooldown * 2^generation`, saturating.
+	pub fn cooldown_for(generation: u32) -> T::BlockNumber {
+		let base: u64 = T::BaseCooldown::get().as_();
+		let factor = 1u64.checked_shl(generation).unwrap_or(u64::max_value());
+		let max: u64 = T::BlockNumber::max_value().as_();
+		<T::BlockNumber as As<u64>>::sa(base.saturating_mul(factor).min(max))
+	}
+
+	/// Use a breeding charge of both parents, count their children and start their cooldowns
+	fn after_breed(kitty_id_1: T::KittyIndex, kitty1: &KittyOf<T>, kitty_id_2: T::KittyIndex, kitty2: &KittyOf<T>, children: u32) {
+		<KittyCharges<T>>::mutate(kitty_id_1, |charges| *charges = charges.saturating_sub(1));
+		<KittyCharges<T>>::mutate(kitty_id_2, |charges| *charges = charges.saturating_sub(1));
+		<OffspringCount<T>>::mutate(kitty_id_1, |count| *count = count.saturating_add(children));
+		<OffspringCount<T>>::mutate(kitty_id_2, |count| *count = count.saturating_add(children));
+
+		let now = <system::Module<T>>::block_number();
+		<KittyCooldownUntil<T>>::insert(kitty_id_1, now.saturating_add(Self::cooldown_for(kitty1.generation)));
+		<KittyCooldownUntil<T>>::insert(kitty_id_2, now.saturating_add(Self::cooldown_for(kitty2.generation)));
+	}
+
+	/// Returns the ids of the children. Usually one, sometimes twins.
+	fn do_breed(sender: &T::AccountId, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> result::Result<Vec<T::KittyIndex>, &'static str> {
+		let (kitty1, kitty2) = Self::breeding_pair(kitty_id_1, kitty_id_2)?;
+
+		ensure!(Self::kitty_owner(&kitty_id_1).map(|owner| owner == *sender).unwrap_or(false), Error::NotOwner.into());
+		ensure!(Self::kitty_owner(&kitty_id_2).map(|owner| owner == *sender).unwrap_or(false), Error::NotOwner.into());
+
+		// Make sure the child can be created before charging the fee
+		Self::next_kitty_id()?;
+		let fee = Self::withdraw_breed_fee(sender)?;
+		T::OnFeePaid::on_unbalanced(fee);
+
+		Self::breed_children(sender, kitty_id_1, &kitty1, kitty_id_2, &kitty2)
+	}
+
+	/// Withdraw the breeding fee, never killing the account of the breeder
+	fn withdraw_breed_fee(sender: &T::AccountId) -> result::Result<NegativeImbalanceOf<T>, &'static str> {
+		let fee = Self::current_breed_fee();
+		let remaining = T::Currency::free_balance(sender).checked_sub(&fee);
+		ensure!(
+			remaining.map(|remaining| remaining >= T::Currency::minimum_balance()).unwrap_or(false),
+			Error::InsufficientBalanceToBreed.into()
+		);
+		Self::withdraw_fee(sender, fee)
+	}
+
+	/// Create the children of a checked and paid breeding. Usually one, sometimes twins.
+	fn breed_children(
+		sender: &T::AccountId,
+		kitty_id_1: T::KittyIndex,
+		kitty1: &KittyOf<T>,
+		kitty_id_2: T::KittyIndex,
+		kitty2: &KittyOf<T>,
+	) -> result::Result<Vec<T::KittyIndex>, &'static str> {
+		let kitty_id = Self::insert_child(sender, kitty_id_1, kitty1, kitty_id_2, kitty2)?;
+		let mut children = vec![kitty_id];
+
+		// Twins are only born if the supply allows
+		let roll = Self::random_value(sender).first().cloned().unwrap_or(u8::max_value());
+		if roll < T::TwinChance::get() && Self::next_kitty_id().is_ok() {
+			children.push(Self::insert_child(sender, kitty_id_1, kitty1, kitty_id_2, kitty2)?);
+		}
+
+		Self::after_breed(kitty_id_1, kitty1, kitty_id_2, kitty2, children.len() as u32);
+
+		Ok(children)
+	}
+
+	/// Returns the ids of the children. The sire price is paid like a sale of the sire.
+	fn do_breed_with_sire(sender: &T::AccountId, kitty_id: T::KittyIndex, sire_id: T::KittyIndex, max_price: MarketBalanceOf<T>) -> result::Result<Vec<T::KittyIndex>, &'static str> {
+		let (kitty, sire) = Self::breeding_pair(kitty_id, sire_id)?;
+
+		ensure!(Self::kitty_owner(&kitty_id).map(|owner| owner == *sender).unwrap_or(false), Error::NotOwner.into());
+
+		let sire_price = Self::sire_price(sire_id);
+		ensure!(sire_price.is_some(), Error::NotForSiring.into());
+
+		let sire_price = sire_price.unwrap();
+		ensure!(max_price >= sire_price, Error::PriceTooLow.into());
+
+		// Make sure the child can be created before any payment
+		Self::next_kitty_id()?;
+
+		let sire_owner = Self::kitty_owner(sire_id).ok_or(Error::InvalidKitty)?;
+		// The fee is taken first so the sire payment is checked against what is left.
+		// It is given back if the sire cannot be paid, as failed calls are not reverted.
+		let fee = Self::withdraw_breed_fee(sender)?;
+		if let Err(e) = Self::ensure_can_pay(sender, &sire_owner, sire_id, sire_price, false) {
+			T::Currency::resolve_creating(sender, fee);
+			return Err(e);
+		}
+		Self::pay_for_kitty(sender, &sire_owner, sire_id, sire_price, false)?;
+		T::OnFeePaid::on_unbalanced(fee);
+
+		Self::breed_children(sender, kitty_id, &kitty, sire_id, &sire)
+	}
+
+	fn insert_child(
+		owner: &T::AccountId,
+		kitty_id_1: T::KittyIndex,
+		kitty1: &KittyOf<T>,
+		kitty_id_2: T::KittyIndex,
+		kitty2: &KittyOf<T>,
+	) -> result::Result<T::KittyIndex, &'static str> {
+		// Make sure the kitty can be created before generating DNA
+		Self::next_kitty_id()?;
+
+		// Generate a random value of DNA length
+		let selector = Self::random_value(owner);
+
+		// Combine parents and selector to create new kitty, favouring the rarer parent
+		let mut new_dna = Self::combine_parents(&kitty1.dna, &kitty2.dna, &selector);
+
+		// Randomly flip some bits
+		let mutation_mask = Self::random_value(owner);
+		let mutation_rate = T::MutationRate::get();
+		for i in 0..new_dna.len() {
+			new_dna[i] = mutate_dna(new_dna[i], mutation_mask[i], selector[i], mutation_rate);
+		}
+
+		let kitty_id = Self::insert_kitty(owner, new_dna, child_generation(kitty1, kitty2))?;
+		<KittyParents<T>>::insert(kitty_id, (kitty_id_1, kitty_id_2));
+		<ChildrenOf<T>>::mutate(parent_pair(kitty_id_1, kitty_id_2), |children| children.push(kitty_id));
+
+		Ok(kitty_id)
+	}
+
+	/// DNA of a child from `BreedingStrategy`, always of `Trait::DnaLength` bytes
+	fn combine_parents(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8> {
+		let weighted_selector = weight_parents_selector(dna1, dna2, selector);
+		let mut dna = T::BreedingStrategy::combine(dna1, dna2, &weighted_selector);
+		// Never trust the strategy with the length, mutations index the DNA by position
+		dna.resize(T::DnaLength::get() as usize, 0);
+		dna
+	}
+
+	/// List a kitty for sale, reserving the listing deposit from the owner if not already reserved
+	fn list_kitty(
+		owner: &T::AccountId,
+		lister: &T::AccountId,
+		kitty_id: T::KittyIndex,
+		price: MarketBalanceOf<T>,
+		expires_at: Option<T::BlockNumber>,
+	) -> result::Result<(), &'static str> {
+		let deposit = T::ListingDeposit::get();
+		if !deposit.is_zero() && !<KittyListingDeposits<T>>::exists(kitty_id) {
+			T::Currency::reserve(owner, deposit)?;
+			<KittyListingDeposits<T>>::insert(kitty_id, deposit);
+		}
+		if !<KittyPrices<T>>::exists(kitty_id) {
+			<ForSale<T>>::mutate(|kitties| kitties.push(kitty_id));
+		}
+		<KittyPrices<T>>::insert(kitty_id, price);
+		<KittyListers<T>>::insert(kitty_id, lister);
+		if let Some(expires_at) = expires_at {
+			<KittyListingExpiry<T>>::insert(kitty_id, expires_at);
+			<ListingsExpiringAt<T>>::mutate(expires_at, |kitties| kitties.push(kitty_id));
+		} else {
+			<KittyListingExpiry<T>>::remove(kitty_id);
+		}
+
+		Self::deposit_event(RawEvent::Ask(owner.clone(), kitty_id, price));
+
+		Ok(())
+	}
+
+	/// Remove all offers for a kitty and unreserve the offered amounts
+	fn refund_offers(kitty_id: T::KittyIndex) {
+		for offerer in <KittyOfferers<T>>::take(kitty_id) {
+			if let Some(amount) = <KittyOffers<T>>::take(&(kitty_id, offerer.clone())) {
+				T::MarketCurrency::unreserve(&offerer, amount);
+			}
+		}
+	}
+
+	/// Remove the listing of a kitty and release the listing deposit
+	/// Remove a swap proposal and its entries in `KittySwapProposals`
+	fn remove_swap_proposal(proposal_id: u64) {
+		if let Some(proposal) = <SwapProposals<T>>::take(proposal_id) {
+			<KittySwapProposals<T>>::mutate(proposal.proposer_kitty, |ids| ids.retain(|id| *id != proposal_id));
+			<KittySwapProposals<T>>::mutate(proposal.target_kitty, |ids| ids.retain(|id| *id != proposal_id));
+		}
+	}
+
+	fn remove_listing(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+		if <KittyPrices<T>>::take(kitty_id).is_some() {
+			<ForSale<T>>::mutate(|kitties| kitties.retain(|&id| id != kitty_id));
+		}
+		<KittyListers<T>>::remove(kitty_id);
+		<KittyListingExpiry<T>>::remove(kitty_id);
+		if let Some(deposit) = <KittyListingDeposits<T>>::take(kitty_id) {
+			T::Currency::unreserve(owner, deposit);
+		}
+	}
+
+	/// Transfer a kitty between accounts that are not frozen. Fails before any change.
+	fn do_transfer(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex) -> result::Result<(), &'static str> {
+		Self::ensure_not_frozen(from)?;
+		Self::ensure_not_frozen(to)?;
+
+		Self::move_kitty(from, to, kitty_id);
+
+		Ok(())
+	}
+
+	/// Move a kitty to a new owner, without checking either account
+	fn move_kitty(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex) {
+		// Listing is made by the owner
+		Self::remove_listing(from, kitty_id);
+
+		<OwnedKittiesList<T>>::remove(&from, kitty_id);
+		<OwnedKittiesList<T>>::append(&to, kitty_id);
+		<KittyOwners<T>>::insert(kitty_id, to);
+		// Any running auction is ended by a change of ownership
+		<KittyAuctions<T>>::remove(kitty_id);
+		// Siring rights are offered by the owner
+		<SireRights<T>>::remove(kitty_id);
+		// Approval is granted by the owner
+		<KittyApprovals<T>>::remove(kitty_id);
+		// Offers are made to the owner
+		Self::refund_offers(kitty_id);
+
+		T::OnKittyTransfer::on_transfer(from, to, kitty_id);
+
+		Self::deposit_event(RawEvent::CollectionChanged(from.clone()));
+		Self::deposit_event(RawEvent::CollectionChanged(to.clone()));
+	}
+}
+
+/// Tests for Kitties module
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use runtime_io::with_externalities;
+	use primitives::{H256, Blake2Hasher};
+	use support::{impl_outer_origin, impl_outer_event, assert_ok, assert_noop, assert_err, parameter_types, traits::Imbalance};
+	use runtime_primitives::{
+		BuildStorage,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize, OnFinalize},
+		testing::{Digest, DigestItem, Header}
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test {}
+	}
+
+	mod kitties {
+		pub use crate::kitties::Event;
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			balances<T>, kitties<T>,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq, Debug)]
+	pub struct Test;
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type Digest = Digest;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type Log = DigestItem;
+	}
+	impl balances::Trait for Test {
+		type Balance = u32;
+		type OnFreeBalanceZero = ();
+		type OnNewAccount = ();
+		type Event = TestEvent;
+
+		type TransactionPayment = ();
+		type DustRemoval = ();
+		type TransferPayment = ();
+	}
+	parameter_types! {
+		pub const MaxBatchSize: u32 = 3;
+		pub const MaxCreateBatchSize: u32 = 5;
+		pub const MutationRate: u8 = 64;
+		pub const MaxAuctionsSettledPerBlock: u32 = 2;
+		pub const FeeCollector: u64 = 99;
+		pub const NullAccount: u64 = 100;
+		pub const BreedFeeStep: u32 = 2;
+		pub const FaucetCooldown: u64 = 10;
+	}
+	thread_local! {
+		static CREATION_FEE: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct CreationFee;
+	impl Get<u32> for CreationFee {
+		fn get() -> u32 {
+			CREATION_FEE.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static BREED_FEE_BASE: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct BreedFeeBase;
+	impl Get<u32> for BreedFeeBase {
+		fn get() -> u32 {
+			BREED_FEE_BASE.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static ROYALTY_PERCENT: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct RoyaltyPercent;
+	impl Get<u32> for RoyaltyPercent {
+		fn get() -> u32 {
+			ROYALTY_PERCENT.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static LISTING_DEPOSIT: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct ListingDeposit;
+	impl Get<u32> for ListingDeposit {
+		fn get() -> u32 {
+			LISTING_DEPOSIT.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static MIN_LISTING_PRICE: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct MinListingPrice;
+	impl Get<u32> for MinListingPrice {
+		fn get() -> u32 {
+			MIN_LISTING_PRICE.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static DNA_LENGTH: RefCell<u32> = RefCell::new(16);
+	}
+	pub struct DnaLength;
+	impl Get<u32> for DnaLength {
+		fn get() -> u32 {
+			DNA_LENGTH.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static MAX_KITTIES: RefCell<u32> = RefCell::new(u32::max_value());
+	}
+	pub struct MaxKitties;
+	impl Get<u32> for MaxKitties {
+		fn get() -> u32 {
+			MAX_KITTIES.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static MARKETPLACE_FEE_PERCENT: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct MarketplaceFeePercent;
+	impl Get<u32> for MarketplaceFeePercent {
+		fn get() -> u32 {
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static BREEDING_CHARGES: RefCell<u32> = RefCell::new(u32::max_value());
+	}
+	pub struct BreedingCharges;
+	impl Get<u32> for BreedingCharges {
+		fn get() -> u32 {
+			BREEDING_CHARGES.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static TWIN_CHANCE: RefCell<u8> = RefCell::new(0);
+	}
+	pub struct TwinChance;
+	impl Get<u8> for TwinChance {
+		fn get() -> u8 {
+			TWIN_CHANCE.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static BASE_COOLDOWN: RefCell<u64> = RefCell::new(0);
+	}
+	pub struct BaseCooldown;
+	impl Get<u64> for BaseCooldown {
+		fn get() -> u64 {
+			BASE_COOLDOWN.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static MAX_GENERATION: RefCell<u32> = RefCell::new(u32::max_value());
+	}
+	pub struct MaxGeneration;
+	impl Get<u32> for MaxGeneration {
+		fn get() -> u32 {
+			MAX_GENERATION.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static INBREEDING_DEPTH: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct InbreedingDepth;
+	impl Get<u32> for InbreedingDepth {
+		fn get() -> u32 {
+			INBREEDING_DEPTH.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static FEES_PAID: RefCell<Vec<u32>> = RefCell::new(vec![]);
+	}
+	pub struct RecordFees;
+	impl OnUnbalanced<NegativeImbalanceOf<Test>> for RecordFees {
+		fn on_unbalanced(amount: NegativeImbalanceOf<Test>) {
+			FEES_PAID.with(|v| v.borrow_mut().push(amount.peek()));
+		}
+	}
+	thread_local! {
+		static RECYCLE_IDS: RefCell<bool> = RefCell::new(false);
+	}
+	pub struct RecycleIds;
+	impl Get<bool> for RecycleIds {
+		fn get() -> bool {
+			RECYCLE_IDS.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static COOLDOWN_RESET_FEE: RefCell<u32> = RefCell::new(0);
+	}
+	pub struct CooldownResetFee;
+	impl Get<u32> for CooldownResetFee {
+		fn get() -> u32 {
+			COOLDOWN_RESET_FEE.with(|v| *v.borrow())
+		}
+	}
+	thread_local! {
+		static AVERAGE_BREEDING: RefCell<bool> = RefCell::new(false);
+		static BREEDINGS: RefCell<Vec<(Vec<u8>, Vec<u8>)>> = RefCell::new(vec![]);
+		static BREEDING_LENGTH: RefCell<Option<usize>> = RefCell::new(None);
+	}
+	/// Default strategy, or averages the parents and records them if `AVERAGE_BREEDING` is set.
+	/// Returns DNA of `BREEDING_LENGTH` bytes if set, to misbehave.
+	pub struct TestBreeding;
+	impl BreedingStrategy for TestBreeding {
+		fn combine(dna1: &[u8], dna2: &[u8], selector: &[u8]) -> Vec<u8> {
+			if let Some(length) = BREEDING_LENGTH.with(|v| *v.borrow()) {
+				return vec![0xff; length];
+			}
+			if !AVERAGE_BREEDING.with(|v| *v.borrow()) {
+				return <()>::combine(dna1, dna2, selector);
+			}
+			BREEDINGS.with(|v| v.borrow_mut().push((dna1.to_vec(), dna2.to_vec())));
+			dna1.iter().zip(dna2).map(|(a, b)| ((*a as u16 + *b as u16) / 2) as u8).collect()
+		}
+	}
+	thread_local! {
+		static TRANSFERS: RefCell<Vec<(u64, u64, u32)>> = RefCell::new(vec![]);
+	}
+	pub struct RecordTransfers;
+	impl OnKittyTransfer<u64, u32> for RecordTransfers {
+		fn on_transfer(from: &u64, to: &u64, kitty_id: u32) {
+			TRANSFERS.with(|v| v.borrow_mut().push((*from, *to, kitty_id)));
+		}
+	}
+	impl Trait for Test {
+		type KittyIndex = u32;
+		type Currency = balances::Module<Test>;
+		type MarketCurrency = balances::Module<Test>;
+		type Event = TestEvent;
+		type MaxBatchSize = MaxBatchSize;
+		type MaxCreateBatchSize = MaxCreateBatchSize;
+		type MutationRate = MutationRate;
+		type CreationFee = CreationFee;
+		type BreedFeeBase = BreedFeeBase;
+		type BreedFeeStep = BreedFeeStep;
+		type OnFeePaid = RecordFees;
+		type RoyaltyPercent = RoyaltyPercent;
+		type ListingDeposit = ListingDeposit;
+		type MinListingPrice = MinListingPrice;
+		type DnaLength = DnaLength;
+		type MaxKitties = MaxKitties;
+		type MaxAuctionsSettledPerBlock = MaxAuctionsSettledPerBlock;
+		type MarketplaceFeePercent = MarketplaceFeePercent;
+		type FeeCollector = FeeCollector;
+		type BreedingCharges = BreedingCharges;
+		type TwinChance = TwinChance;
+		type BaseCooldown = BaseCooldown;
+		type CooldownResetFee = CooldownResetFee;
+		type MaxGeneration = MaxGeneration;
+		type InbreedingDepth = InbreedingDepth;
+		type RecycleIds = RecycleIds;
+		type FaucetCooldown = FaucetCooldown;
+		type NullAccount = NullAccount;
+		type BreedingStrategy = TestBreeding;
+		type OnKittyTransfer = RecordTransfers;
+	}
+	type System = system::Module<Test>;
+	type Balances = balances::Module<Test>;
+	type KittyModule = Module<Test>;
+	type OwnedKittiesTest = OwnedKitties<Test>;
+
+	// This function basically just builds a genesis storage key/value store according to
+	// our desired mockup.
+	fn new_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(balances::GenesisConfig::<Test> {
+			transaction_base_fee: 0,
+			transaction_byte_fee: 0,
+			balances: vec![(1, 10), (2, 20), (3, 30), (4, 40), (5, 50), (6, 60)],
+			existential_deposit: 0,
+			transfer_fee: 0,
+			creation_fee: 0,
+			vesting: vec![],
+		}.build_storage().unwrap().0);
+		t.into()
+	}
+
+	fn last_event() -> TestEvent {
+		System::events().pop().map(|record| record.event).expect("Event expected")
+	}
+
+	#[test]
+	fn owned_kitties_can_append_values() {
+		with_externalities(&mut new_test_ext(), || {
+			OwnedKittiesList::<Test>::append(&0, 1);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: Some(1),
+				next: Some(1),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
+				prev: None,
+				next: None,
+			}));
+
+			OwnedKittiesList::<Test>::append(&0, 2);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: Some(2),
+				next: Some(1),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
+				prev: None,
+				next: Some(2),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem::<Test> {
+				prev: Some(1),
+				next: None,
+			}));
+
+			OwnedKittiesList::<Test>::append(&0, 3);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: Some(3),
+				next: Some(1),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
+				prev: None,
+				next: Some(2),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem::<Test> {
+				prev: Some(1),
+				next: Some(3),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
+				prev: Some(2),
+				next: None,
+			}));
+		});
+	}
+
+	#[test]
+	fn owned_kitties_can_remove_values() {
+		with_externalities(&mut new_test_ext(), || {
+			OwnedKittiesList::<Test>::append(&0, 1);
+			OwnedKittiesList::<Test>::append(&0, 2);
+			OwnedKittiesList::<Test>::append(&0, 3);
+
+			OwnedKittiesList::<Test>::remove(&0, 2);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: Some(3),
+				next: Some(1),
+			}));
 
 			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
 				prev: None,
-				next: Some(2),
+				next: Some(3),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
+				prev: Some(1),
+				next: None,
+			}));
+
+			OwnedKittiesList::<Test>::remove(&0, 1);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: Some(3),
+				next: Some(3),
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
+				prev: None,
+				next: None,
+			}));
+
+			OwnedKittiesList::<Test>::remove(&0, 3);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
+				prev: None,
+				next: None,
+			}));
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+
+			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+		});
+	}
+
+	#[test]
+	fn owned_kitties_count_is_tracked() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(KittyModule::owned_kitties_count(0), 0);
+
+			OwnedKittiesList::<Test>::append(&0, 1);
+			assert_eq!(KittyModule::owned_kitties_count(0), 1);
+
+			OwnedKittiesList::<Test>::append(&0, 2);
+			assert_eq!(KittyModule::owned_kitties_count(0), 2);
+
+			OwnedKittiesList::<Test>::append(&0, 3);
+			assert_eq!(KittyModule::owned_kitties_count(0), 3);
+			assert_eq!(OwnedKittiesList::<Test>::len(&0), 3);
+
+			OwnedKittiesList::<Test>::remove(&0, 2);
+			assert_eq!(KittyModule::owned_kitties_count(0), 2);
+
+			// Removing a value not in the list does nothing
+			OwnedKittiesList::<Test>::remove(&0, 2);
+			assert_eq!(KittyModule::owned_kitties_count(0), 2);
+
+			OwnedKittiesList::<Test>::remove(&0, 1);
+			assert_eq!(KittyModule::owned_kitties_count(0), 1);
+
+			OwnedKittiesList::<Test>::remove(&0, 3);
+			assert_eq!(KittyModule::owned_kitties_count(0), 0);
+			assert_eq!(OwnedKittiesList::<Test>::len(&0), 0);
+		});
+	}
+
+	fn assert_linked_list_invariants(account: u64, expected: &[u32]) {
+		let head = OwnedKittiesTest::get(&(account, None)).unwrap_or(KittyLinkedItem::<Test> {
+			prev: None,
+			next: None,
+		});
+
+		// Forward from head, every node must be linked back to its predecessor
+		let mut forward = Vec::new();
+		let mut prev = None;
+		let mut next = head.next;
+		while let Some(value) = next {
+			assert!(forward.len() < expected.len(), "Forward walk does not return to head");
+			let item = OwnedKittiesTest::get(&(account, Some(value))).expect("Linked node must exist");
+			assert_eq!(item.prev, prev);
+			forward.push(value);
+			prev = Some(value);
+			next = item.next;
+		}
+		assert_eq!(head.prev, prev);
+
+		// Backward from head, every node must be linked forward to its successor
+		let mut backward = Vec::new();
+		let mut next = None;
+		let mut prev = head.prev;
+		while let Some(value) = prev {
+			assert!(backward.len() < expected.len(), "Backward walk does not return to head");
+			let item = OwnedKittiesTest::get(&(account, Some(value))).expect("Linked node must exist");
+			assert_eq!(item.next, next);
+			backward.push(value);
+			next = Some(value);
+			prev = item.prev;
+		}
+		assert_eq!(head.next, next);
+		backward.reverse();
+
+		assert_eq!(forward, expected.to_vec());
+		assert_eq!(backward, expected.to_vec());
+		assert_eq!(OwnedKittiesList::<Test>::len(&account), expected.len() as u64);
+	}
+
+	#[test]
+	fn linked_list_invariants_hold_for_random_operations() {
+		with_externalities(&mut new_test_ext(), || {
+			// Simple deterministic LCG so failures are reproducible
+			let mut seed: u64 = 42;
+			let mut next_random = move |max: u64| {
+				seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+				(seed >> 33) % max
+			};
+
+			let mut lists: Vec<Vec<u32>> = vec![Vec::new(), Vec::new()];
+			for _ in 0..500 {
+				let account = next_random(2);
+				let value = next_random(16) as u32;
+				let list = &mut lists[account as usize];
+				if next_random(2) == 0 {
+					// Values can only be appended once
+					if !list.contains(&value) {
+						OwnedKittiesList::<Test>::append(&account, value);
+						list.push(value);
+					}
+				} else {
+					OwnedKittiesList::<Test>::remove(&account, value);
+					list.retain(|&v| v != value);
+				}
+
+				assert_linked_list_invariants(0, &lists[0]);
+				assert_linked_list_invariants(1, &lists[1]);
+			}
+		});
+	}
+
+	#[test]
+	fn owned_kitties_count_follows_transfer() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::owned_kitties_count(1), 2);
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::owned_kitties_count(1), 1);
+			assert_eq!(KittyModule::owned_kitties_count(2), 1);
+		});
+	}
+
+	#[test]
+	fn owned_kitties_iter_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(KittyModule::owned_kitties_iter(&0), vec![]);
+
+			OwnedKittiesList::<Test>::append(&0, 1);
+			OwnedKittiesList::<Test>::append(&0, 2);
+			OwnedKittiesList::<Test>::append(&0, 3);
+
+			assert_eq!(KittyModule::owned_kitties_iter(&0), vec![1, 2, 3]);
+
+			OwnedKittiesList::<Test>::remove(&0, 2);
+
+			assert_eq!(KittyModule::owned_kitties_iter(&0), vec![1, 3]);
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![]);
+		});
+	}
+
+	#[test]
+	fn random_value_uses_nonce() {
+		with_externalities(&mut new_test_ext(), || {
+			assert_eq!(KittyModule::nonce(), 0);
+
+			let dna1 = KittyModule::random_value(&1);
+			let dna2 = KittyModule::random_value(&1);
+
+			assert_ne!(dna1, dna2);
+			assert_eq!(KittyModule::nonce(), 2);
+		});
+	}
+
+	#[test]
+	fn genesis_kitties_works() {
+		let mut t = system::GenesisConfig::<Test>::default().build_storage().unwrap().0;
+		t.extend(GenesisConfig::<Test> {
+			kitties: vec![(1, vec![1; 16]), (2, vec![2; 16])],
+		}.build_storage().unwrap().0);
+		let mut ext: runtime_io::TestExternalities<Blake2Hasher> = t.into();
+		with_externalities(&mut ext, || {
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(KittyModule::kitty(0).unwrap().dna, [1; 16]);
+			assert_eq!(KittyModule::kitty(1).unwrap().dna, [2; 16]);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(2));
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![0]);
+			assert_eq!(KittyModule::owned_kitties_iter(&2), vec![1]);
+		});
+	}
+
+	#[test]
+	fn basic_setup_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Verify Initial Storage
+			assert_eq!(KittyModule::kitties_count(), 0);
+			assert!(KittyModule::kitty(0).is_none());
+			assert_eq!(KittyModule::kitty_owner(0), None);
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(2), 20);
+		});
+	}
+
+	#[test]
+	fn create_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Call Functions
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert!(KittyModule::kitty(0).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn age_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty(0).unwrap().birth, 3);
+			assert_eq!(KittyModule::birth_block(0), Some(3));
+			assert_eq!(KittyModule::age(0), Some(0));
+			System::set_block_number(10);
+			assert_eq!(KittyModule::age(0), Some(7));
+			assert_eq!(KittyModule::age(1), None);
+			assert_eq!(KittyModule::birth_block(1), None);
+		});
+	}
+
+	#[test]
+	fn kitty_fields_are_populated() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			System::set_block_number(5);
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 2));
+			// Verify Storage
+			let kitty = KittyModule::kitty(0).unwrap();
+			assert_eq!(kitty.generation, 0);
+			assert_eq!(kitty.birth, 2);
+			let kitty = KittyModule::kitty(2).unwrap();
+			assert_eq!(kitty.generation, 1);
+			assert_eq!(kitty.birth, 5);
+			let kitty = KittyModule::kitty(3).unwrap();
+			assert_eq!(kitty.generation, 2);
+			assert_eq!(kitty.birth, 5);
+		});
+	}
+
+	#[test]
+	fn create_with_seed_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Call Functions
+			assert_ok!(KittyModule::create_with_seed(Origin::signed(1), b"kitty".to_vec()));
+			assert_ok!(KittyModule::create_with_seed(Origin::signed(1), b"kitty".to_vec()));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			let dna1 = KittyModule::kitty(0).unwrap().dna;
+			let dna2 = KittyModule::kitty(1).unwrap().dna;
+			assert_ne!(dna1, dna2);
+
+			// Same seed and nonce yield the same DNA
+			<Nonce<Test>>::put(0);
+			assert_eq!(KittyModule::random_value_with_seed(&1, b"kitty"), dna1);
+			assert_eq!(KittyModule::random_value_with_seed(&1, b"kitty"), dna2);
+
+			// Different seed yields different DNA
+			<Nonce<Test>>::put(0);
+			assert_ne!(KittyModule::random_value_with_seed(&1, b"other"), dna1);
+		});
+	}
+
+	#[test]
+	fn create_with_seed_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Call Functions
+			assert_noop!(KittyModule::create_with_seed(Origin::signed(1), vec![0; MAX_SEED_LENGTH + 1]), Error::SeedTooLong.into());
+			assert_ok!(KittyModule::create_with_seed(Origin::signed(1), vec![0; MAX_SEED_LENGTH]));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+		});
+	}
+
+	#[test]
+	fn set_paused_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::set_paused(Origin::signed(1), true), "bad origin: expected to be a root origin");
+			assert_ok!(KittyModule::set_paused(Origin::ROOT, true));
+			// Verify Storage
+			assert!(KittyModule::paused());
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Paused(true)));
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::Paused.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::Paused.into());
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 2, 0), Error::Paused.into());
+			assert_noop!(KittyModule::ask(Origin::signed(1), 0, Some(10), None), Error::Paused.into());
+			assert_noop!(KittyModule::buy(Origin::signed(2), 0, 10), Error::Paused.into());
+			// Unpause
+			assert_ok!(KittyModule::set_paused(Origin::ROOT, false));
+			assert!(!KittyModule::paused());
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::kitties_count(), 3);
+		});
+	}
+
+	#[test]
+	fn minting_restriction_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::set_minting_restricted(Origin::signed(1), true), "bad origin: expected to be a root origin");
+			assert_noop!(KittyModule::set_minter(Origin::signed(1), 1, true), "bad origin: expected to be a root origin");
+			// Call Functions
+			assert_ok!(KittyModule::set_minting_restricted(Origin::ROOT, true));
+			// Verify Storage
+			assert!(KittyModule::minting_restricted());
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::NotMinter.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::NotMinter.into());
+			// Approve minter
+			assert_ok!(KittyModule::set_minter(Origin::ROOT, 1, true));
+			assert!(KittyModule::is_minter(1));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_noop!(KittyModule::create(Origin::signed(2)), Error::NotMinter.into());
+			// Disapprove minter
+			assert_ok!(KittyModule::set_minter(Origin::ROOT, 1, false));
+			assert!(!KittyModule::is_minter(1));
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::NotMinter.into());
+			// Lift the restriction
+			assert_ok!(KittyModule::set_minting_restricted(Origin::ROOT, false));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_eq!(KittyModule::kitties_count(), 5);
+		});
+	}
+
+	#[test]
+	fn create_charges_creation_fee() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			CREATION_FEE.with(|v| *v.borrow_mut() = 5);
+			let total_issuance = Balances::total_issuance();
+			// Call Functions
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::total_issuance(), total_issuance - 5);
+		});
+	}
+
+	#[test]
+	fn create_fails_without_creation_fee() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			CREATION_FEE.with(|v| *v.borrow_mut() = 15);
+			// Call Functions
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::InsufficientBalance.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 0);
+			assert!(KittyModule::kitty(0).is_none());
+			assert_eq!(KittyModule::kitty_owner(0), None);
+			assert_eq!(Balances::free_balance(1), 10);
+			// Account 2 can afford it
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::free_balance(2), 5);
+		});
+	}
+
+	#[test]
+	fn create_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			<KittiesCount<Test>>::put(u32::max_value());
+			// Call Functions
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::CountOverflow.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), u32::max_value());
+			assert!(KittyModule::kitty(0).is_none());
+			assert_eq!(KittyModule::kitty_owner(0), None);
+			assert_eq!(KittyModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn kitties_count_overflow_is_handled() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			<KittiesCount<Test>>::put(u32::max_value() - 3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			<KittiesCount<Test>>::put(u32::max_value() - 1);
+			// Call Functions
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::kitties_count(), u32::max_value());
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::CountOverflow.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), u32::max_value() - 3, u32::max_value() - 2), Error::CountOverflow.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), u32::max_value());
+			assert_eq!(KittyModule::kitty_owner(u32::max_value() - 1), Some(1));
+			assert!(KittyModule::kitty(u32::max_value()).is_none());
+			assert_eq!(KittyModule::kitty_owner(u32::max_value()), None);
+			assert_eq!(KittyModule::owned_kitties_count(1), 3);
+		});
+	}
+
+	#[test]
+	fn transfer_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert!(KittyModule::kitty(0).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn gift_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), None));
+			// Call Functions
+			assert_noop!(KittyModule::gift(Origin::signed(2), 3, 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::gift(Origin::signed(1), 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Gifted(1, 2, 0)));
+		});
+	}
+
+	#[test]
+	fn transfer_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::transfer(Origin::signed(2), 2, 0), Error::NotOwner.into());
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 2, 1), Error::NotOwner.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert!(KittyModule::kitty(0).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn transfer_to_self_fails() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 1, 0), Error::TransferToSelf.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![0, 1]);
+		});
+	}
+
+	#[test]
+	fn breed_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 3);
+			assert!(KittyModule::kitty(0).is_some());
+			assert!(KittyModule::kitty(1).is_some());
+			assert!(KittyModule::kitty(2).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			assert_eq!(KittyModule::kitty_owner(2), Some(1));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(KittyModule::kitty_price(1), None);
+			assert_eq!(KittyModule::kitty_price(2), None);
+			// Verify Event
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Bred(1, 0, 1, 2)));
+		});
+	}
+
+	#[test]
+	fn breed_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Call Functions
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 0), Error::SameParents.into());
+			assert_noop!(KittyModule::breed(Origin::signed(2), 0, 1), Error::NotOwner.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::NotOwner.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 2, 1), Error::InvalidKitty.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 2), Error::InvalidKitty.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert!(KittyModule::kitty(0).is_some());
+			assert!(KittyModule::kitty(1).is_some());
+			assert!(KittyModule::kitty(2).is_none());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(2));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(KittyModule::kitty_price(1), None);
+		});
+	}
+
+	#[test]
+	fn mutate_dna_works() {
+		assert_eq!(mutate_dna(0b1010_1010, 0b1111_0000, 10, 64), 0b0101_1010);
+		assert_eq!(mutate_dna(0b1010_1010, 0b1111_0000, 63, 64), 0b0101_1010);
+		assert_eq!(mutate_dna(0b1010_1010, 0b1111_0000, 64, 64), 0b1010_1010);
+		assert_eq!(mutate_dna(0b1010_1010, 0b1111_0000, 10, 0), 0b1010_1010);
+	}
+
+	#[test]
+	fn breed_mutates_dna() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0b0000_1111; 16], 0), Ok(0));
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0b1111_0000; 16], 0), Ok(1));
+			let nonce = KittyModule::nonce();
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Replay the same random values
+			<Nonce<Test>>::put(nonce);
+			let selector = KittyModule::random_value(&1);
+			let mutation_mask = KittyModule::random_value(&1);
+			// Verify Storage
+			let dna = KittyModule::kitty(2).unwrap().dna;
+			for i in 0..16 {
+				// The second parent is rarer
+				let combined = combine_dna(0b0000_1111, 0b1111_0000, weight_selector(selector[i], 240, 480));
+				if selector[i] < MutationRate::get() {
+					assert_eq!(dna[i], combined ^ mutation_mask[i]);
+				} else {
+					assert_eq!(dna[i], combined);
+				}
+			}
+		});
+	}
+
+	#[test]
+	fn dna_rarity_works() {
+		assert_eq!(dna_rarity(&[0; 16]), 0);
+		assert_eq!(dna_rarity(&[0xff; 16]), 720);
+		assert_eq!(dna_rarity(&[0x01; 16]), 16);
+		assert_eq!(dna_rarity(&[0x10; 16]), 32);
+		assert_eq!(dna_rarity(&[0x21, 0x03, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xf0]), 5 + 3 + 30);
+	}
+
+	#[test]
+	fn dna_traits_works() {
+		let dna = [0x01, 0xab, 0x3c, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+		assert_eq!(dna_fur_color(&dna), 0xab);
+		assert_eq!(dna_eye_shape(&dna), 0x03);
+		assert_eq!(dna_pattern(&dna), 0x0c);
+		assert_eq!(dna_fur_color(&[]), 0);
+		assert_eq!(dna_eye_shape(&[0x01, 0xab]), 0);
+	}
+
+	#[test]
+	fn trait_accessors_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			let mut dna = vec![0; 16];
+			dna[1] = 0x42;
+			dna[2] = 0x9e;
+			assert_eq!(KittyModule::insert_kitty(&1, dna, 0), Ok(0));
+			// Verify
+			assert_eq!(KittyModule::fur_color(0), Some(0x42));
+			assert_eq!(KittyModule::eye_shape(0), Some(0x09));
+			assert_eq!(KittyModule::pattern(0), Some(0x0e));
+			assert_eq!(KittyModule::fur_color(1), None);
+		});
+	}
+
+	#[test]
+	fn rarity_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0x12; 16], 0), Ok(0));
+			// Verify
+			assert_eq!(KittyModule::rarity(0), Some(64));
+			assert_eq!(KittyModule::rarity(1), None);
+		});
+	}
+
+	#[test]
+	fn ask_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert!(KittyModule::kitty(0).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_price(0), Some(10));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Ask(1, 0, 10)));
+		});
+	}
+
+	#[test]
+	fn delist_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, None, None));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Delisted(1, 0)));
+		});
+	}
+
+	#[test]
+	fn listings_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), None));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(6), None));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 2, Some(7), None));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(8), None));
+			// Verify Storage
+			assert_eq!(KittyModule::listings(), vec![(0, 5), (1, 8), (2, 7)]);
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(2), 1, 8));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 2, None, None));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 3, Some(9), None));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 3, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::listings(), vec![(3, 9)]);
+			assert_eq!(KittyModule::for_sale(), vec![3]);
+		});
+	}
+
+	#[test]
+	fn buy_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 15));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert!(KittyModule::kitty(0).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(Balances::free_balance(2), 10);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Sold(1, 2, 0, 10, 15)));
+		});
+	}
+
+	#[test]
+	fn buy_fails_with_inconsistent_owner() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			<KittyOwners<Test>>::insert(0, 3);
+			// Call Functions
+			assert_noop!(KittyModule::buy(Origin::signed(2), 0, 10), Error::InconsistentOwner.into());
+			// Verify Storage
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(Balances::free_balance(3), 30);
+		});
+	}
+
+	#[test]
+	fn start_auction_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::auction(0), Some(DutchAuction {
+				start_price: 20,
+				end_price: 10,
+				start_block: 1,
+				end_block: 11,
+			}));
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+		});
+	}
+
+	#[test]
+	fn start_auction_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(10), None));
+			// Call Functions
+			assert_noop!(KittyModule::start_auction(Origin::signed(2), 0, 20, 10, 10), Error::NotOwner.into());
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 10, 20, 10), Error::InvalidAuctionPrice.into());
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 0), Error::InvalidAuctionDuration.into());
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 1, 20, 10, 10), Error::KittyListed.into());
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10));
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10), Error::InAuction.into());
+			assert_noop!(KittyModule::ask(Origin::signed(1), 0, Some(10), None), Error::InAuction.into());
+		});
+	}
+
+	#[test]
+	fn auction_price_decreases() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10));
+			// Verify Price
+			assert_eq!(KittyModule::auction_price(0), Some(20));
+			System::set_block_number(4);
+			assert_eq!(KittyModule::auction_price(0), Some(17));
+			System::set_block_number(6);
+			assert_eq!(KittyModule::auction_price(0), Some(15));
+			System::set_block_number(11);
+			assert_eq!(KittyModule::auction_price(0), Some(10));
+			System::set_block_number(20);
+			assert_eq!(KittyModule::auction_price(0), Some(10));
+			assert_eq!(KittyModule::auction_price(1), None);
+		});
+	}
+
+	#[test]
+	fn bid_auction_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10));
+			System::set_block_number(6);
+			// Call Functions
+			assert_noop!(KittyModule::bid_auction(Origin::signed(2), 0, 14), Error::PriceTooLow.into());
+			assert_ok!(KittyModule::bid_auction(Origin::signed(2), 0, 20));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::auction(0), None);
+			assert_eq!(Balances::free_balance(1), 25);
+			assert_eq!(Balances::free_balance(2), 5);
+			assert_noop!(KittyModule::bid_auction(Origin::signed(3), 0, 20), Error::NotInAuction.into());
+		});
+	}
+
+	#[test]
+	fn bid_auction_pays_marketplace_fee_and_royalty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 20);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::start_auction(Origin::signed(2), 0, 20, 20, 10));
+			// Call Functions
+			assert_noop!(KittyModule::bid_auction(Origin::signed(2), 0, 20), Error::BuyOwnKitty.into());
+			assert_ok!(KittyModule::bid_auction(Origin::signed(3), 0, 20));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(Balances::free_balance(99), 2);
+			assert_eq!(Balances::free_balance(1), 14);
+			assert_eq!(Balances::free_balance(2), 34);
+			assert_eq!(Balances::free_balance(3), 10);
+		});
+	}
+
+	#[test]
+	fn start_auction_rejects_overflowing_duration() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, u64::max_value()), Error::InvalidAuctionDuration.into());
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, u64::max_value() - 2));
+			// Verify Storage
+			assert_eq!(KittyModule::auction(0).map(|a| a.end_block), Some(u64::max_value()));
+		});
+	}
+
+	#[test]
+	fn cancel_auction_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10));
+			// Call Functions
+			assert_noop!(KittyModule::cancel_auction(Origin::signed(2), 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::cancel_auction(Origin::signed(1), 0));
+			// Verify Storage
+			assert_eq!(KittyModule::auction(0), None);
+			assert_noop!(KittyModule::cancel_auction(Origin::signed(1), 0), Error::NotInAuction.into());
+			assert_noop!(KittyModule::bid_auction(Origin::signed(2), 0, 20), Error::NotInAuction.into());
+		});
+	}
+
+	#[test]
+	fn start_english_auction_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::start_english_auction(Origin::signed(2), 0, 5, 10), Error::NotOwner.into());
+			assert_noop!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 1), Error::InvalidAuctionDuration.into());
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::english_auction(0), Some(EnglishAuction {
+				reserve: 5,
+				end_block: 10,
+			}));
+			assert_eq!(KittyModule::ending(10), vec![0]);
+			assert_eq!(KittyModule::highest_bid(0), None);
+			assert_noop!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10), Error::InAuction.into());
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 20, 10, 10), Error::InAuction.into());
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 2, 0), Error::InAuction.into());
+		});
+	}
+
+	#[test]
+	fn place_bid_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			// Call Functions
+			assert_noop!(KittyModule::place_bid(Origin::signed(2), 1, 5), Error::NotInAuction.into());
+			assert_noop!(KittyModule::place_bid(Origin::signed(1), 0, 5), Error::OwnerCannotBid.into());
+			assert_noop!(KittyModule::place_bid(Origin::signed(2), 0, 4), Error::PriceTooLow.into());
+			assert_ok!(KittyModule::place_bid(Origin::signed(2), 0, 6));
+			assert_noop!(KittyModule::place_bid(Origin::signed(3), 0, 6), Error::PriceTooLow.into());
+			System::set_block_number(10);
+			assert_noop!(KittyModule::place_bid(Origin::signed(3), 0, 8), Error::AuctionEnded.into());
+		});
+	}
+
+	#[test]
+	fn english_auction_settles_to_highest_bidder() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			// Call Functions
+			assert_ok!(KittyModule::place_bid(Origin::signed(2), 0, 6));
+			assert_eq!(Balances::reserved_balance(2), 6);
+			assert_eq!(Balances::free_balance(2), 14);
+
+			assert_ok!(KittyModule::place_bid(Origin::signed(3), 0, 8));
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(Balances::reserved_balance(3), 8);
+
+			assert_ok!(KittyModule::place_bid(Origin::signed(2), 0, 10));
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 30);
+			assert_eq!(Balances::reserved_balance(2), 10);
+			assert_eq!(KittyModule::highest_bid(0), Some((2, 10)));
+
+			// Not ended yet
+			KittyModule::on_finalize(9);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::english_auction(0), None);
+			assert_eq!(KittyModule::ending(10), vec![]);
+			assert_eq!(KittyModule::highest_bid(0), None);
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 10);
+		});
+	}
+
+	#[test]
+	fn english_auction_pays_marketplace_fee_and_royalty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 20);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(2), 0, 5, 10));
+			assert_ok!(KittyModule::place_bid(Origin::signed(3), 0, 20));
+			// Call Functions
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(Balances::free_balance(99), 2);
+			assert_eq!(Balances::free_balance(1), 14);
+			assert_eq!(Balances::free_balance(2), 34);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 10);
+		});
+	}
+
+	#[test]
+	fn english_auction_refunds_bid_if_fee_cannot_be_paid() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			<balances::ExistentialDeposit<Test>>::put(5);
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			assert_ok!(KittyModule::place_bid(Origin::signed(3), 0, 20));
+			// Call Functions
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
+			// Verify Storage
+			// A fee of 2 cannot create the fee collector account
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::EnglishAuctionEnded(1, 0)));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 30);
+		});
+	}
+
+	#[test]
+	fn english_auction_without_bid_keeps_owner() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			// Call Functions
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::english_auction(0), None);
+			assert_eq!(KittyModule::ending(10), vec![]);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+		});
+	}
+
+	#[test]
+	fn make_offer_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::make_offer(Origin::signed(2), 0, 5));
+			// Verify Storage
+			assert_eq!(KittyModule::offers(&(0, 2)), Some(5));
+			assert_eq!(Balances::reserved_balance(2), 5);
+			assert_eq!(Balances::free_balance(2), 15);
+			// Replace the offer
+			assert_ok!(KittyModule::make_offer(Origin::signed(2), 0, 8));
+			assert_eq!(KittyModule::offers(&(0, 2)), Some(8));
+			assert_eq!(Balances::reserved_balance(2), 8);
+			assert_eq!(Balances::free_balance(2), 12);
+		});
+	}
+
+	#[test]
+	fn make_offer_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::make_offer(Origin::signed(2), 1, 5), Error::InvalidKitty.into());
+			assert_noop!(KittyModule::make_offer(Origin::signed(1), 0, 5), Error::OwnerCannotBid.into());
+			assert!(KittyModule::make_offer(Origin::signed(2), 0, 50).is_err());
+			// Verify Storage
+			assert_eq!(KittyModule::offers(&(0, 2)), None);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 20);
+		});
+	}
+
+	#[test]
+	fn accept_offer_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::make_offer(Origin::signed(2), 0, 5));
+			assert_ok!(KittyModule::make_offer(Origin::signed(3), 0, 8));
+			// Call Functions
+			assert_noop!(KittyModule::accept_offer(Origin::signed(2), 0, 3), Error::NotOwner.into());
+			assert_noop!(KittyModule::accept_offer(Origin::signed(1), 0, 4), Error::OfferNotFound.into());
+			assert_ok!(KittyModule::accept_offer(Origin::signed(1), 0, 3));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::offers(&(0, 3)), None);
+			assert_eq!(Balances::free_balance(1), 18);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 22);
+			// Other offers are refunded
+			assert_eq!(KittyModule::offers(&(0, 2)), None);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(KittyModule::offerers(0), vec![]);
+		});
+	}
+
+	#[test]
+	fn accept_offer_pays_marketplace_fee_and_royalty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 20);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::make_offer(Origin::signed(3), 0, 20));
+			// Call Functions
+			assert_ok!(KittyModule::accept_offer(Origin::signed(2), 0, 3));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(Balances::free_balance(99), 2);
+			assert_eq!(Balances::free_balance(1), 14);
+			assert_eq!(Balances::free_balance(2), 34);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 10);
+		});
+	}
+
+	#[test]
+	fn withdraw_offer_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::make_offer(Origin::signed(2), 0, 5));
+			// Call Functions
+			assert_noop!(KittyModule::withdraw_offer(Origin::signed(3), 0), Error::OfferNotFound.into());
+			assert_ok!(KittyModule::withdraw_offer(Origin::signed(2), 0));
+			// Verify Storage
+			assert_eq!(KittyModule::offers(&(0, 2)), None);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+		});
+	}
+
+	#[test]
+	fn transfer_batch_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::transfer_batch(Origin::signed(1), 2, vec![0, 2]));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert!(OwnedKittiesTest::exists(&(2, Some(0))));
+			assert!(OwnedKittiesTest::exists(&(2, Some(2))));
+			assert!(!OwnedKittiesTest::exists(&(1, Some(0))));
+			assert!(!OwnedKittiesTest::exists(&(1, Some(2))));
+		});
+	}
+
+	#[test]
+	fn transfer_batch_is_all_or_nothing() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::transfer_batch(Origin::signed(1), 3, vec![0, 1, 2]), Error::NotOwner.into());
+			assert_noop!(KittyModule::transfer_batch(Origin::signed(1), 3, vec![0, 2, 0]), Error::DuplicatedKitty.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(2));
+			assert_eq!(KittyModule::kitty_owner(2), Some(1));
+		});
+	}
+
+	#[test]
+	fn transfer_batch_is_bounded() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			for _ in 0..4 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+			}
+			// Call Functions
+			assert_noop!(KittyModule::transfer_batch(Origin::signed(1), 2, vec![0, 1, 2, 3]), Error::TooManyKitties.into());
+			assert_ok!(KittyModule::transfer_batch(Origin::signed(1), 2, vec![0, 1, 2]));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert_eq!(KittyModule::kitty_owner(3), Some(1));
+		});
+	}
+
+	#[test]
+	fn create_batch_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Call Functions
+			assert_ok!(KittyModule::create_batch(Origin::signed(1), 5));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 6);
+			for i in 1..6 {
+				assert!(KittyModule::kitty(i).is_some());
+				assert_eq!(KittyModule::kitty_owner(i), Some(1));
+			}
+			for i in 1..6 {
+				for j in (i + 1)..6 {
+					assert_ne!(KittyModule::kitty(i).unwrap().dna, KittyModule::kitty(j).unwrap().dna);
+				}
+			}
+		});
+	}
+
+	#[test]
+	fn create_batch_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Call Functions
+			assert_noop!(KittyModule::create_batch(Origin::signed(1), 6), Error::TooManyKitties.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 0);
+
+			// Setup
+			<KittiesCount<Test>>::put(u32::max_value() - 2);
+			// Call Functions
+			assert_noop!(KittyModule::create_batch(Origin::signed(1), 3), Error::CountOverflow.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), u32::max_value() - 2);
+			assert_eq!(KittyModule::kitty_owner(u32::max_value() - 2), None);
+			assert_eq!(KittyModule::owned_kitties_count(1), 0);
+			// The batch that fits is fully minted
+			assert_ok!(KittyModule::create_batch(Origin::signed(1), 2));
+			assert_eq!(KittyModule::kitties_count(), u32::max_value());
+			assert_eq!(KittyModule::owned_kitties_count(1), 2);
+		});
+	}
+
+	#[test]
+	fn create_batch_is_all_or_nothing() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			CREATION_FEE.with(|v| *v.borrow_mut() = 4);
+			MAX_KITTIES.with(|v| *v.borrow_mut() = 3);
+			// Call Functions
+			assert_noop!(KittyModule::create_batch(Origin::signed(1), 3), Error::InsufficientBalance.into());
+			assert_noop!(KittyModule::create_batch(Origin::signed(3), 4), Error::MaxSupplyReached.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 0);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(3), 30);
+			// The whole fee is charged once the batch fits
+			assert_ok!(KittyModule::create_batch(Origin::signed(3), 3));
+			assert_eq!(KittyModule::owned_kitties_count(3), 3);
+			assert_eq!(Balances::free_balance(3), 18);
+		});
+	}
+
+	#[test]
+	fn offer_sire_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::offer_sire(Origin::signed(2), 0, Some(5)), Error::NotOwner.into());
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			// Verify Storage
+			assert_eq!(KittyModule::sire_price(0), Some(5));
+			// Withdraw the offer
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, None));
+			assert_eq!(KittyModule::sire_price(0), None);
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			// Call Functions
+			assert_ok!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 6));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 3);
+			assert!(KittyModule::kitty(2).is_some());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::kitty_owner(1), Some(2));
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert_eq!(Balances::free_balance(1), 15);
+			assert_eq!(Balances::free_balance(2), 15);
+			// Siring offer stays until withdrawn
+			assert_eq!(KittyModule::sire_price(0), Some(5));
+			// Verify Event
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Bred(2, 1, 0, 2)));
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Call Functions
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 5), Error::NotForSiring.into());
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 4), Error::PriceTooLow.into());
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(3), 1, 0, 5), Error::NotOwner.into());
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 1, 5), Error::SameParents.into());
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 2, 5), Error::InvalidKitty.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(2), 20);
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_charges_breed_fee() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			assert_eq!(KittyModule::current_breed_fee(), 6);
+			// Call Functions
+			assert_ok!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 5));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert_eq!(Balances::free_balance(1), 15);
+			assert_eq!(Balances::free_balance(2), 9);
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_refunds_fee_when_sire_cannot_be_paid() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(15)));
+			// Call Functions
+			// 20 covers the sire price or the fee of 6, but not both
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 15), Error::InsufficientBalance.into());
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, true));
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 5), Error::AccountFrozen.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::free_balance(2), 20);
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_can_produce_twins() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			TWIN_CHANCE.with(|v| *v.borrow_mut() = 128);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)));
+			// Force a nonce where the twin roll hits. The roll follows the selector and mutation mask.
+			let mut nonce = KittyModule::nonce();
+			loop {
+				<Nonce<Test>>::put(nonce + 2);
+				if KittyModule::random_value(&2)[0] < 128 {
+					break;
+				}
+				nonce += 1;
+			}
+			<Nonce<Test>>::put(nonce);
+			// Call Functions
+			assert_ok!(KittyModule::breed_with_sire(Origin::signed(2), 1, 0, 5));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 4);
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert_eq!(KittyModule::kitty_owner(3), Some(2));
+			assert_eq!(KittyModule::offspring_count(0), 2);
+			assert_eq!(Balances::free_balance(2), 15);
+			// Verify Event
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Bred(2, 1, 0, 3)));
+		});
+	}
+
+	#[test]
+	fn transfer_from_works_for_owner() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::transfer_from(Origin::signed(1), 1, 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+		});
+	}
+
+	#[test]
+	fn transfer_from_works_for_approved() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::approve(Origin::signed(2), 2, 0), Error::NotApproved.into());
+			assert_ok!(KittyModule::approve(Origin::signed(1), 2, 0));
+			assert_eq!(KittyModule::approved(0), Some(2));
+			// Call Functions
+			assert_ok!(KittyModule::transfer_from(Origin::signed(2), 1, 3, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::approved(0), None);
+			assert_noop!(KittyModule::transfer_from(Origin::signed(2), 3, 2, 0), Error::NotApproved.into());
+		});
+	}
+
+	#[test]
+	fn transfer_from_works_for_operator() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, true));
+			assert!(KittyModule::is_operator(&(1, 2)));
+			// Call Functions
+			assert_ok!(KittyModule::transfer_from(Origin::signed(2), 1, 3, 0));
+			assert_ok!(KittyModule::approve(Origin::signed(2), 4, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::approved(1), Some(4));
+			// Revoke operator
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, false));
+			assert!(!KittyModule::is_operator(&(1, 2)));
+			assert_noop!(KittyModule::transfer_from(Origin::signed(2), 1, 3, 1), Error::NotApproved.into());
+		});
+	}
+
+	#[test]
+	fn transfer_from_handles_basic_errors() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::transfer_from(Origin::signed(2), 1, 2, 0), Error::NotApproved.into());
+			assert_noop!(KittyModule::transfer_from(Origin::signed(1), 2, 3, 0), Error::NotOwner.into());
+			assert_noop!(KittyModule::set_approval_for_all(Origin::signed(1), 1, true), Error::ApproveSelf.into());
+			assert_noop!(KittyModule::approve(Origin::signed(1), 2, 1), Error::InvalidKitty.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+		});
+	}
+
+	#[test]
+	fn buy_pays_royalty_to_breeder() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::breeder(0), 1);
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::breeder(0), 1);
+			assert_eq!(Balances::free_balance(1), 11);
+			assert_eq!(Balances::free_balance(2), 29);
+			assert_eq!(Balances::free_balance(3), 20);
+		});
+	}
+
+	#[test]
+	fn royalty_split_rounds_to_seller() {
+		with_externalities(&mut new_test_ext(), || {
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_eq!(KittyModule::royalty_split(15), (1, 14));
+			assert_eq!(KittyModule::royalty_split(9), (0, 9));
+			assert_eq!(KittyModule::royalty_split(100), (10, 90));
+		});
+	}
+
+	#[test]
+	fn buy_by_breeder_seller_pays_no_royalty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 10));
+			// Verify Storage
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(Balances::free_balance(2), 10);
+		});
+	}
+
+	#[test]
+	fn ask_reserves_listing_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// List
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_eq!(KittyModule::listing_deposit(0), Some(2));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_eq!(Balances::free_balance(1), 8);
+			// Relist does not reserve again
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(15), None));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_eq!(Balances::free_balance(1), 8);
+			// Delist
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, None, None));
+			assert_eq!(KittyModule::listing_deposit(0), None);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 10);
+			// Sale
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 10));
+			assert_eq!(KittyModule::listing_deposit(0), None);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(Balances::free_balance(2), 10);
+		});
+	}
+
+	#[test]
+	fn transfer_removes_listing() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(KittyModule::listing_deposit(0), None);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(1), 10);
+		});
+	}
+
+	#[test]
+	fn ask_fails_without_listing_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 20);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert!(KittyModule::ask(Origin::signed(1), 0, Some(10), None).is_err());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(KittyModule::listing_deposit(0), None);
+			assert_eq!(Balances::reserved_balance(1), 0);
+		});
+	}
+
+	#[test]
+	fn ask_enforces_min_listing_price() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MIN_LISTING_PRICE.with(|v| *v.borrow_mut() = 5);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::ask(Origin::signed(1), 0, Some(4), None), Error::PriceTooLow.into());
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), None));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), Some(5));
+			// Delisting is always allowed
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, None, None));
+			assert_eq!(KittyModule::kitty_price(0), None);
+		});
+	}
+
+	#[test]
+	fn kitty_details_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0x12; 16], 0), Ok(0));
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0x13; 16], 0), Ok(1));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Verify
+			assert_eq!(KittyModule::kitty_details(0), Some(KittyDetails {
+				dna: vec![0x12; 16],
+				owner: 1,
+				price: Some(10),
+				generation: 0,
+				gender: KittyGender::Male,
+			}));
+			assert_eq!(KittyModule::kitty_details(1).map(|details| details.gender), Some(KittyGender::Female));
+			assert_eq!(KittyModule::kitty_details(2), None);
+		});
+	}
+
+	#[test]
+	fn configurable_dna_length_works() {
+		for length in &[16, 32] {
+			with_externalities(&mut new_test_ext(), || {
+				// Setup
+				DNA_LENGTH.with(|v| *v.borrow_mut() = *length);
+				// Call Functions
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+				assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+				// Verify Storage
+				for kitty_id in 0..3 {
+					assert_eq!(KittyModule::kitty(kitty_id).unwrap().dna.len(), *length as usize);
+				}
+				assert_ne!(KittyModule::kitty(0).unwrap().dna, KittyModule::kitty(1).unwrap().dna);
+			});
+		}
+	}
+
+	#[test]
+	fn delist_all_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 2);
+			for kitty_id in 0..4 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+				if kitty_id < 3 {
+					assert_ok!(KittyModule::ask(Origin::signed(1), kitty_id, Some(10), None));
+				}
+			}
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 4, Some(10), None));
+			assert_eq!(Balances::reserved_balance(1), 6);
+			// Call Functions
+			assert_ok!(KittyModule::delist_all(Origin::signed(1)));
+			// Verify Storage
+			for kitty_id in 0..4 {
+				assert_eq!(KittyModule::kitty_price(kitty_id), None);
+			}
+			assert_eq!(KittyModule::listings(), vec![(4, 10)]);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Delisted(1, 2)));
+		});
+	}
+
+	#[test]
+	fn buy_fails_after_listing_expired() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), Some(3)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(5), Some(3)));
+			assert_eq!(KittyModule::listing_expiry(0), Some(3));
+			// Call Functions
+			System::set_block_number(3);
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 5));
+			System::set_block_number(4);
+			assert_noop!(KittyModule::buy(Origin::signed(2), 1, 5), Error::ListingExpired.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::listing_expiry(0), None);
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			assert_noop!(KittyModule::ask(Origin::signed(1), 1, Some(5), Some(3)), Error::InvalidListingExpiry.into());
+		});
+	}
+
+	#[test]
+	fn expired_listings_are_removed() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), Some(3)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(5), Some(3)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 2, Some(5), Some(3)));
+			// Relisted without expiry
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(6), None));
+			// Call Functions
+			KittyModule::on_initialize(3);
+			assert_eq!(KittyModule::listings(), vec![(0, 5), (1, 6), (2, 5)]);
+			KittyModule::on_initialize(4);
+			// Verify Storage
+			assert_eq!(KittyModule::listings(), vec![(1, 6)]);
+			assert_eq!(KittyModule::listing_expiry(0), None);
+			assert_eq!(KittyModule::listings_expiring_at(3), vec![]);
+		});
+	}
+
+	#[test]
+	fn swap_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 1, Some(10), None));
+			// Call Functions
+			assert_noop!(KittyModule::propose_swap(Origin::signed(1), 1, 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::propose_swap(Origin::signed(1), 0, 1));
+			assert_eq!(KittyModule::swap_proposal(0), Some(SwapProposal {
+				proposer: 1,
+				proposer_kitty: 0,
+				target_kitty: 1,
+			}));
+			assert_noop!(KittyModule::accept_swap(Origin::signed(1), 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::accept_swap(Origin::signed(2), 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			assert_eq!(KittyModule::kitty_price(1), None);
+			assert_eq!(KittyModule::swap_proposal(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Swapped(1, 2, 0, 1)));
+		});
+	}
+
+	#[test]
+	fn swap_fails_if_kitty_sold() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::propose_swap(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 10));
+			// Call Functions
+			assert_noop!(KittyModule::accept_swap(Origin::signed(2), 0), Error::NotOwner.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::kitty_owner(1), Some(2));
+		});
+	}
+
+	#[test]
+	fn cancel_swap_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::propose_swap(Origin::signed(1), 0, 1));
+			// Call Functions
+			assert_noop!(KittyModule::cancel_swap(Origin::signed(2), 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::cancel_swap(Origin::signed(1), 0));
+			// Verify Storage
+			assert_eq!(KittyModule::swap_proposal(0), None);
+			assert_noop!(KittyModule::accept_swap(Origin::signed(2), 0), Error::SwapNotFound.into());
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+		});
+	}
+
+	#[test]
+	fn kitties_of_paged_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			for kitty_id in 0..10 {
+				OwnedKittiesList::<Test>::append(&0, kitty_id);
+			}
+			// Verify
+			assert_eq!(KittyModule::kitties_of_paged(&0, None, 3), vec![0, 1, 2]);
+			assert_eq!(KittyModule::kitties_of_paged(&0, Some(2), 3), vec![3, 4, 5]);
+			assert_eq!(KittyModule::kitties_of_paged(&0, Some(5), 3), vec![6, 7, 8]);
+			assert_eq!(KittyModule::kitties_of_paged(&0, Some(8), 3), vec![9]);
+			assert_eq!(KittyModule::kitties_of_paged(&0, Some(9), 3), vec![]);
+			assert_eq!(KittyModule::kitties_of_paged(&0, None, 0), vec![]);
+			assert_eq!(KittyModule::kitties_of_paged(&1, None, 3), vec![]);
+		});
+	}
+
+	#[test]
+	fn max_kitties_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MAX_KITTIES.with(|v| *v.borrow_mut() = 3);
+			// Call Functions
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 3);
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::MaxSupplyReached.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::MaxSupplyReached.into());
+			assert_noop!(KittyModule::create_batch(Origin::signed(1), 2), Error::MaxSupplyReached.into());
+		});
+	}
+
+	#[test]
+	fn english_auctions_settle_with_per_block_limit() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(1);
+			for kitty_id in 0..5 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+				assert_ok!(KittyModule::start_english_auction(Origin::signed(1), kitty_id, 5, 10));
+			}
+			assert_eq!(KittyModule::ending(10), vec![0, 1, 2, 3, 4]);
+			// Call Functions
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
+			// Verify Storage
+			assert_eq!(KittyModule::ending(10), vec![]);
+			assert_eq!(KittyModule::ending(11), vec![2, 3, 4]);
+			assert_eq!(KittyModule::english_auction(1), None);
+			assert!(KittyModule::english_auction(2).is_some());
+
+			System::set_block_number(11);
+			KittyModule::on_finalize(11);
+			assert_eq!(KittyModule::ending(12), vec![4]);
+			assert!(KittyModule::english_auction(4).is_some());
+
+			System::set_block_number(12);
+			KittyModule::on_finalize(12);
+			assert_eq!(KittyModule::ending(13), vec![]);
+			for kitty_id in 0..5 {
+				assert_eq!(KittyModule::english_auction(kitty_id), None);
+			}
+		});
+	}
+
+	#[test]
+	fn buy_pays_marketplace_fee() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(20), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 25));
+			// Verify Storage
+			assert_eq!(Balances::free_balance(99), 2);
+			assert_eq!(Balances::free_balance(1), 28);
+			assert_eq!(Balances::free_balance(2), 0);
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+		});
+	}
+
+	#[test]
+	fn buy_pays_marketplace_fee_and_royalty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 60);
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 50);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 10));
+			// Verify Storage
+			// Royalty is capped by what is left after the fee
+			assert_eq!(Balances::free_balance(99), 6);
+			assert_eq!(Balances::free_balance(1), 14);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(Balances::free_balance(3), 20);
+		});
+	}
+
+	#[test]
+	fn breed_consumes_charges() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BREEDING_CHARGES.with(|v| *v.borrow_mut() = 1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::charges(0), 1);
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::charges(0), 0);
+			assert_eq!(KittyModule::charges(1), 0);
+			assert_eq!(KittyModule::charges(3), 1);
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 2), Error::NoChargesLeft.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 2, 1), Error::NoChargesLeft.into());
+			assert_ok!(KittyModule::breed(Origin::signed(1), 2, 3));
+		});
+	}
+
+	#[test]
+	fn owned_kitties_contains_and_position() {
+		with_externalities(&mut new_test_ext(), || {
+			OwnedKittiesList::<Test>::append(&0, 1);
+			OwnedKittiesList::<Test>::append(&0, 2);
+			OwnedKittiesList::<Test>::append(&0, 3);
+			OwnedKittiesList::<Test>::append(&1, 4);
+
+			assert!(OwnedKittiesList::<Test>::contains(&0, 2));
+			assert!(!OwnedKittiesList::<Test>::contains(&0, 4));
+			assert!(!OwnedKittiesList::<Test>::contains(&2, 1));
+
+			assert_eq!(OwnedKittiesList::<Test>::position(&0, 1), Some(0));
+			assert_eq!(OwnedKittiesList::<Test>::position(&0, 2), Some(1));
+			assert_eq!(OwnedKittiesList::<Test>::position(&0, 3), Some(2));
+			assert_eq!(OwnedKittiesList::<Test>::position(&0, 4), None);
+			assert_eq!(OwnedKittiesList::<Test>::position(&1, 4), Some(0));
+
+			OwnedKittiesList::<Test>::remove(&0, 1);
+			assert!(!OwnedKittiesList::<Test>::contains(&0, 1));
+			assert_eq!(OwnedKittiesList::<Test>::position(&0, 3), Some(1));
+		});
+	}
+
+	#[test]
+	fn owned_kitties_remove_non_member_is_noop() {
+		with_externalities(&mut new_test_ext(), || {
+			OwnedKittiesList::<Test>::append(&0, 1);
+			OwnedKittiesList::<Test>::append(&0, 2);
+			OwnedKittiesList::<Test>::append(&1, 3);
+			OwnedKittiesList::<Test>::append(&1, 4);
+
+			// 3 is owned by 1, not 0
+			OwnedKittiesList::<Test>::remove(&0, 3);
+			OwnedKittiesList::<Test>::remove(&2, 1);
+
+			assert_eq!(OwnedKittiesList::<Test>::items(&0), vec![1, 2]);
+			assert_eq!(OwnedKittiesList::<Test>::items(&1), vec![3, 4]);
+			assert_eq!(OwnedKittiesList::<Test>::len(&0), 2);
+			assert_eq!(OwnedKittiesList::<Test>::len(&1), 2);
+			assert_eq!(OwnedKittiesList::<Test>::len(&2), 0);
+			assert_eq!(OwnedKittiesTest::get(&(2, None)), None);
+		});
+	}
+
+	#[test]
+	fn buy_exact_rejects_changed_price() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Price is lowered after the buyer read it
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(8), None));
+			// Call Functions
+			assert_noop!(KittyModule::buy_exact(Origin::signed(2), 0, 10), Error::PriceChanged.into());
+			assert_noop!(KittyModule::buy_exact(Origin::signed(2), 1, 10), Error::NotForSale.into());
+			// `buy` with the same price would have succeeded
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::free_balance(2), 12);
+		});
+	}
+
+	#[test]
+	fn buy_exact_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_ok!(KittyModule::buy_exact(Origin::signed(2), 0, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Sold(1, 2, 0, 10, 10)));
+		});
+	}
+
+	#[test]
+	fn created_count_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::created_count(1), 3);
+			assert_eq!(KittyModule::created_count(2), 1);
+			assert_eq!(KittyModule::created_count(3), 0);
+		});
+	}
+
+	#[test]
+	fn created_event_has_dna() {
+		with_externalities(&mut new_test_ext(), || {
+			// Call Functions
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Verify
+			let dna = KittyModule::kitty(0).unwrap().dna;
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Created(1, 0, dna)));
+		});
+	}
+
+	#[test]
+	fn stored_kitty_equals_expected() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(7);
+			// Call Functions
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0x42; 16], 3), Ok(0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty(0), Some(Kitty {
+				dna: vec![0x42; 16],
+				generation: 3,
+				birth: 7,
+			}));
+		});
+	}
+
+	#[test]
+	fn breed_can_produce_twins() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			TWIN_CHANCE.with(|v| *v.borrow_mut() = 128);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Force a nonce where the twin roll hits. The roll follows the selector and mutation mask.
+			let mut nonce = KittyModule::nonce();
+			loop {
+				<Nonce<Test>>::put(nonce + 2);
+				if KittyModule::random_value(&1)[0] < 128 {
+					break;
+				}
+				nonce += 1;
+			}
+			<Nonce<Test>>::put(nonce);
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 4);
+			assert_eq!(KittyModule::kitty_owner(2), Some(1));
+			assert_eq!(KittyModule::kitty_owner(3), Some(1));
+			assert_eq!(KittyModule::kitty(3).unwrap().generation, 1);
+			assert_eq!(KittyModule::charges(0), u32::max_value() - 1);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Bred(1, 0, 1, 3)));
+		});
+	}
+
+	#[test]
+	fn kitty_exists_works() {
+		with_externalities(&mut new_test_ext(), || {
+			assert!(!KittyModule::kitty_exists(0));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert!(KittyModule::kitty_exists(0));
+			assert!(!KittyModule::kitty_exists(1));
+		});
+	}
+
+	#[test]
+	fn breed_fails_for_listed_kitty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(10), None));
+			// Call Functions
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::BreedListed.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 1, 0), Error::BreedListed.into());
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, None, None));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 3);
+		});
+	}
+
+	#[test]
+	fn breed_with_sire_fails_for_listed_kitty() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::offer_sire(Origin::signed(2), 1, Some(5)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Call Functions
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(1), 0, 1, 5), Error::BreedListed.into());
+			assert!(!KittyModule::can_breed(0));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, None, None));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 1, Some(10), None));
+			assert_noop!(KittyModule::breed_with_sire(Origin::signed(1), 0, 1, 5), Error::BreedListed.into());
+			assert_ok!(KittyModule::ask(Origin::signed(2), 1, None, None));
+			assert_ok!(KittyModule::breed_with_sire(Origin::signed(1), 0, 1, 5));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(2), Some(1));
+		});
+	}
+
+	#[test]
+	fn cooldown_for_scales_with_generation() {
+		with_externalities(&mut new_test_ext(), || {
+			BASE_COOLDOWN.with(|v| *v.borrow_mut() = 5);
+			assert_eq!(KittyModule::cooldown_for(0), 5);
+			assert_eq!(KittyModule::cooldown_for(1), 10);
+			assert_eq!(KittyModule::cooldown_for(3), 40);
+			assert_eq!(KittyModule::cooldown_for(62), u64::max_value());
+			assert_eq!(KittyModule::cooldown_for(200), u64::max_value());
+		});
+	}
+
+	#[test]
+	fn breed_respects_cooldown() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BASE_COOLDOWN.with(|v| *v.borrow_mut() = 5);
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::cooldown_until(0), 6);
+			assert_eq!(KittyModule::cooldown_until(1), 6);
+			assert_err!(KittyModule::breed(Origin::signed(1), 0, 2), Error::BreedCooldown.as_str());
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::BreedFailedCooldown(0, 6)));
+			assert_err!(KittyModule::breed(Origin::signed(1), 2, 1), Error::BreedCooldown.as_str());
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::BreedFailedCooldown(1, 6)));
+			// Generation 1 child waits twice as long
+			assert_ok!(KittyModule::breed(Origin::signed(1), 3, 2));
+			assert_eq!(KittyModule::cooldown_until(3), 11);
+			assert_eq!(KittyModule::cooldown_until(2), 6);
+			System::set_block_number(6);
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+		});
+	}
+
+	#[test]
+	fn migrate_old_kitties() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			support::storage::put(&<Kitties<Test>>::key_for(0), &[1u8; 16]);
+			support::storage::put(&<Kitties<Test>>::key_for(1), &[2u8; 16]);
+			<KittiesCount<Test>>::put(2);
+			assert_eq!(KittyModule::storage_version(), 0);
+			// Call Functions
+			System::set_block_number(5);
+			KittyModule::on_initialize(5);
+			// Verify Storage
+			assert_eq!(KittyModule::storage_version(), STORAGE_VERSION);
+			assert_eq!(KittyModule::kitty(0), Some(Kitty {
+				dna: vec![1; 16],
+				generation: 0,
+				birth: 0,
 			}));
+			assert_eq!(KittyModule::kitty(1).unwrap().dna, vec![2; 16]);
+			assert_eq!(KittyModule::charges(1), BreedingCharges::get());
+			// Migrated kitties are usable
+			<KittyOwners<Test>>::insert(0, 1);
+			<KittyOwners<Test>>::insert(1, 1);
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_eq!(KittyModule::kitty(2).unwrap().generation, 1);
+		});
+	}
+
+	#[test]
+	fn sale_info_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			// Verify
+			assert_eq!(KittyModule::sale_info(0), Some((1, 10)));
+			assert_eq!(KittyModule::sale_info(1), None);
+			assert_eq!(KittyModule::sale_info(2), None);
+		});
+	}
+
+	#[test]
+	fn on_kitty_transfer_is_called() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(10), None));
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 10));
+			assert_ok!(KittyModule::gift(Origin::signed(3), 4, 0));
+			// Verify
+			assert_eq!(TRANSFERS.with(|v| v.borrow().clone()), vec![(1, 2, 0), (2, 3, 0), (3, 4, 0)]);
+		});
+	}
+
+	#[test]
+	fn preview_breed_matches_breed() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			let nonce = KittyModule::nonce();
+			let selector = KittyModule::random_value(&1);
+			<Nonce<Test>>::put(nonce);
+			// Call Functions
+			let preview = KittyModule::preview_breed(0, 1, selector.clone()).unwrap();
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify
+			let dna = KittyModule::kitty(2).unwrap().dna;
+			for i in 0..16 {
+				// Bytes not mutated are identical
+				if selector[i] >= MutationRate::get() {
+					assert_eq!(dna[i], preview[i]);
+				}
+			}
+			assert_eq!(KittyModule::preview_breed(0, 3, selector), None);
+		});
+	}
+
+	#[test]
+	fn locked_kitty_cannot_be_transferred_sold_or_bred() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(10), None));
+
+			// Call Functions
+			assert_noop!(KittyModule::set_lock(Origin::signed(2), 0, true), Error::NotOwner.into());
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 0, true));
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 1, true));
+
+			// Verify Storage
+			assert!(KittyModule::is_locked(0));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::LockSet(1, 1, true)));
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 2, 0), Error::KittyLocked.into());
+			assert_noop!(KittyModule::ask(Origin::signed(1), 0, Some(10), None), Error::KittyLocked.into());
+			assert_noop!(KittyModule::buy(Origin::signed(2), 1, 10), Error::KittyLocked.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::KittyLocked.into());
+
+			// Unlocking restores all actions
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 0, false));
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 1, false));
+			assert!(!KittyModule::is_locked(0));
+			assert_ok!(KittyModule::buy(Origin::signed(2), 1, 10));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+		});
+	}
+
+	#[test]
+	fn ask_works_for_operator() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::ask(Origin::signed(2), 0, Some(10), None), Error::NotApproved.into());
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, true));
+			// Call Functions
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(10), None));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), Some(10));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Ask(1, 0, 10)));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, None, None));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Delisted(1, 0)));
+		});
+	}
+
+	#[test]
+	fn create_for_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_noop!(KittyModule::create_for(Origin::signed(1), 2), "bad origin: expected to be a root origin");
+			// Call Functions
+			assert_ok!(KittyModule::create_for(Origin::ROOT, 2));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::owned_kitties_iter(&2), vec![0]);
+			assert_eq!(KittyModule::owned_kitties_count(1), 0);
+			let dna = KittyModule::kitty(0).unwrap().dna;
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Created(2, 0, dna)));
+		});
+	}
+
+	#[test]
+	fn breed_fails_beyond_max_generation() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			MAX_GENERATION.with(|v| *v.borrow_mut() = 1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty(3).unwrap().generation, 1);
+			assert_noop!(KittyModule::breed(Origin::signed(1), 2, 3), Error::MaxGenerationReached.into());
+		});
+	}
+
+	#[test]
+	fn all_kitties_skips_missing_kitties() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			for _ in 0..4 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+			}
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Burn kitty 1
+			<Kitties<Test>>::remove(1);
+			<KittyOwners<Test>>::remove(1);
+			OwnedKittiesList::<Test>::remove(&1, 1);
+			// Verify
+			assert_eq!(KittyModule::all_kitties(0, 10), vec![(0, 1), (2, 1), (3, 1), (4, 2)]);
+			assert_eq!(KittyModule::all_kitties(0, 2), vec![(0, 1)]);
+			assert_eq!(KittyModule::all_kitties(2, 2), vec![(2, 1), (3, 1)]);
+			assert_eq!(KittyModule::all_kitties(5, 10), vec![]);
+		});
+	}
+
+	#[test]
+	fn can_breed_works() {
+		with_externalities(&mut new_test_ext(), || {
+			BASE_COOLDOWN.with(|v| *v.borrow_mut() = 5);
+			assert!(!KittyModule::can_breed(0));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert!(KittyModule::can_breed(0));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem::<Test> {
-				prev: Some(1),
-				next: None,
-			}));
+			// Locked
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 0, true));
+			assert!(!KittyModule::can_breed(0));
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 0, false));
+			assert!(KittyModule::can_breed(0));
 
-			OwnedKittiesList::<Test>::append(&0, 3);
+			// Listed
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert!(!KittyModule::can_breed(0));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, None, None));
+			assert!(KittyModule::can_breed(0));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: Some(3),
-				next: Some(1),
-			}));
+			// Cooling down
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert!(!KittyModule::can_breed(0));
+			System::set_block_number(KittyModule::cooldown_until(0));
+			assert!(KittyModule::can_breed(0));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
-				prev: None,
-				next: Some(2),
-			}));
+	/// A second runtime pricing kitties in a separate balances instance
+	mod market_currency {
+		use super::*;
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), Some(KittyLinkedItem::<Test> {
-				prev: Some(1),
-				next: Some(3),
-			}));
+		impl_outer_origin! {
+			pub enum Origin for MarketTest {}
+		}
+
+		mod kitties {
+			pub use crate::kitties::Event;
+		}
+
+		impl_outer_event! {
+			pub enum MarketTestEvent for MarketTest {
+				balances<T>, balances Instance1<T>, kitties<T>,
+			}
+		}
+
+		#[derive(Clone, Eq, PartialEq, Debug)]
+		pub struct MarketTest;
+		impl system::Trait for MarketTest {
+			type Origin = Origin;
+			type Index = u64;
+			type BlockNumber = u64;
+			type Hash = H256;
+			type Hashing = BlakeTwo256;
+			type Digest = Digest;
+			type AccountId = u64;
+			type Lookup = IdentityLookup<Self::AccountId>;
+			type Header = Header;
+			type Event = MarketTestEvent;
+			type Log = DigestItem;
+		}
+		impl balances::Trait for MarketTest {
+			type Balance = u32;
+			type OnFreeBalanceZero = ();
+			type OnNewAccount = ();
+			type Event = MarketTestEvent;
+
+			type TransactionPayment = ();
+			type DustRemoval = ();
+			type TransferPayment = ();
+		}
+		impl balances::Trait<balances::Instance1> for MarketTest {
+			type Balance = u128;
+			type OnFreeBalanceZero = ();
+			type OnNewAccount = ();
+			type Event = MarketTestEvent;
+
+			type TransactionPayment = ();
+			type DustRemoval = ();
+			type TransferPayment = ();
+		}
+		/// Same as `MinListingPrice`, in the larger market balance
+		pub struct MarketMinListingPrice;
+		impl Get<u128> for MarketMinListingPrice {
+			fn get() -> u128 {
+				MIN_LISTING_PRICE.with(|v| *v.borrow() as u128)
+			}
+		}
+		impl Trait for MarketTest {
+			type KittyIndex = u32;
+			type Currency = balances::Module<MarketTest>;
+			type MarketCurrency = balances::Module<MarketTest, balances::Instance1>;
+			type Event = MarketTestEvent;
+			type MaxBatchSize = MaxBatchSize;
+			type MaxCreateBatchSize = MaxCreateBatchSize;
+			type MutationRate = MutationRate;
+			type CreationFee = CreationFee;
+			type BreedFeeBase = BreedFeeBase;
+			type BreedFeeStep = BreedFeeStep;
+			type OnFeePaid = ();
+			type RoyaltyPercent = RoyaltyPercent;
+			type ListingDeposit = ListingDeposit;
+			type MinListingPrice = MarketMinListingPrice;
+			type DnaLength = DnaLength;
+			type MaxKitties = MaxKitties;
+			type MaxAuctionsSettledPerBlock = MaxAuctionsSettledPerBlock;
+			type MarketplaceFeePercent = MarketplaceFeePercent;
+			type FeeCollector = FeeCollector;
+			type BreedingCharges = BreedingCharges;
+			type TwinChance = TwinChance;
+			type BaseCooldown = BaseCooldown;
+			type CooldownResetFee = CooldownResetFee;
+			type MaxGeneration = MaxGeneration;
+			type InbreedingDepth = InbreedingDepth;
+			type RecycleIds = RecycleIds;
+			type FaucetCooldown = FaucetCooldown;
+			type NullAccount = NullAccount;
+			type BreedingStrategy = ();
+			type OnKittyTransfer = ();
+		}
+		type NativeBalances = balances::Module<MarketTest>;
+		type MarketBalances = balances::Module<MarketTest, balances::Instance1>;
+		type MarketKittyModule = Module<MarketTest>;
+
+		fn new_market_test_ext() -> runtime_io::TestExternalities<Blake2Hasher> {
+			let mut t = system::GenesisConfig::<MarketTest>::default().build_storage().unwrap().0;
+			t.extend(balances::GenesisConfig::<MarketTest> {
+				transaction_base_fee: 0,
+				transaction_byte_fee: 0,
+				balances: vec![(1, 10), (2, 20)],
+				existential_deposit: 0,
+				transfer_fee: 0,
+				creation_fee: 0,
+				vesting: vec![],
+			}.build_storage().unwrap().0);
+			t.extend(balances::GenesisConfig::<MarketTest, balances::Instance1> {
+				transaction_base_fee: 0,
+				transaction_byte_fee: 0,
+				balances: vec![(1, 100), (2, 100)],
+				existential_deposit: 0,
+				transfer_fee: 0,
+				creation_fee: 0,
+				vesting: vec![],
+			}.build_storage().unwrap().0);
+			t.into()
+		}
+
+		#[test]
+		fn buy_works_with_market_currency() {
+			with_externalities(&mut new_market_test_ext(), || {
+				// Setup
+				LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 5);
+				assert_ok!(MarketKittyModule::create(Origin::signed(1)));
+				assert_ok!(MarketKittyModule::ask(Origin::signed(1), 0, Some(50), None));
+				// Listing deposit is reserved in the native currency
+				assert_eq!(NativeBalances::reserved_balance(1), 5);
+				// Call Functions
+				assert_ok!(MarketKittyModule::buy(Origin::signed(2), 0, 50));
+				// Verify Storage
+				assert_eq!(MarketKittyModule::kitty_owner(0), Some(2));
+				assert_eq!(MarketBalances::free_balance(1), 150);
+				assert_eq!(MarketBalances::free_balance(2), 50);
+				assert_eq!(NativeBalances::free_balance(1), 10);
+				assert_eq!(NativeBalances::reserved_balance(1), 0);
+				assert_eq!(NativeBalances::free_balance(2), 20);
+			});
+		}
+
+		#[test]
+		fn auction_price_does_not_overflow_at_max_price() {
+			with_externalities(&mut new_market_test_ext(), || {
+				// Setup
+				system::Module::<MarketTest>::set_block_number(1);
+				assert_ok!(MarketKittyModule::create(Origin::signed(1)));
+				let price = u128::max_value();
+				assert_ok!(MarketKittyModule::start_auction(Origin::signed(1), 0, price, 0, 10));
+				// Verify
+				system::Module::<MarketTest>::set_block_number(4);
+				// 3 / 10 of the price rounded down, `price % 10` is 5
+				assert_eq!(MarketKittyModule::auction_price(0), Some(price - price / 10 * 3 - 1));
+				system::Module::<MarketTest>::set_block_number(11);
+				assert_eq!(MarketKittyModule::auction_price(0), Some(0));
+			});
+		}
+
+		#[test]
+		fn sale_split_does_not_overflow_at_max_price() {
+			with_externalities(&mut new_market_test_ext(), || {
+				// Setup
+				ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 10);
+				MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 2);
+				assert_ok!(MarketKittyModule::create(Origin::signed(1)));
+				assert_ok!(MarketKittyModule::transfer(Origin::signed(1), 2, 0));
+				let price = u128::max_value();
+				// Verify
+				assert_eq!(MarketKittyModule::royalty_split(price), (price / 10, price - price / 10));
+				assert_eq!(MarketKittyModule::marketplace_fee(price), price / 50);
+				assert_eq!(MarketKittyModule::sale_payments(&2, 0, price), vec![
+					(99, price / 50),
+					(1, price / 10),
+					(2, price - price / 50 - price / 10),
+				]);
+			});
+		}
+	}
+
+	#[test]
+	fn offspring_count_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(KittyModule::offspring_count(0), 2);
+			assert_eq!(KittyModule::offspring_count(1), 2);
+			assert_eq!(KittyModule::offspring_count(2), 0);
+			assert_eq!(KittyModule::offspring_count(3), 0);
+		});
+	}
+
+	#[test]
+	fn owners_of_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Verify
+			assert_eq!(KittyModule::owners_of(vec![]), vec![]);
+			assert_eq!(KittyModule::owners_of(vec![1, 5, 0, 1]), vec![Some(2), None, Some(1), Some(2)]);
+		});
+	}
+
+	#[test]
+	fn buy_fails_atomically_near_existential_deposit() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			<balances::ExistentialDeposit<Test>>::put(5);
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(18), None));
+			// Buyer would be left with 2
+			assert_noop!(KittyModule::buy(Origin::signed(2), 0, 18), Error::WouldKillAccount.into());
+			// Fee of 1 cannot create the fee collector account
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(15), None));
+			assert_noop!(KittyModule::buy(Origin::signed(2), 0, 15), Error::PaymentBelowMinimum.into());
+			// Call Functions
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 0);
+			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 15));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(Balances::free_balance(1), 25);
+			assert_eq!(Balances::free_balance(2), 5);
+		});
+	}
+
+	#[test]
+	fn buy_fails_if_operator_lister_is_revoked() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, true));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(10), None));
+			assert_eq!(KittyModule::lister(0), Some(2));
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, false));
+			// Call Functions
+			assert_noop!(KittyModule::buy(Origin::signed(3), 0, 10), Error::ListerNotApproved.into());
+			// Owner is paid when the operator is approved again
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, true));
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 10));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			assert_eq!(KittyModule::lister(0), None);
+			assert_eq!(Balances::free_balance(1), 20);
+			assert_eq!(Balances::free_balance(2), 20);
+		});
+	}
+
+	#[test]
+	fn abandon_and_adopt_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_noop!(KittyModule::adopt(Origin::signed(2), 0), Error::NotAbandoned.into());
+			assert_noop!(KittyModule::abandon(Origin::signed(2), 0), Error::NotOwner.into());
+			// Call Functions
+			assert_ok!(KittyModule::abandon(Origin::signed(1), 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(100));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Abandoned(1, 0)));
+			// Adopt by another account
+			assert_ok!(KittyModule::adopt(Origin::signed(2), 0));
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::owned_kitties_iter(&2), vec![0]);
+			assert_eq!(KittyModule::owned_kitties_count(100), 0);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Adopted(2, 0)));
+			assert_noop!(KittyModule::adopt(Origin::signed(3), 0), Error::NotAbandoned.into());
+		});
+	}
+
+	#[test]
+	fn breed_fee_scales_with_supply() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 3);
+			assert_eq!(KittyModule::current_breed_fee(), 3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::current_breed_fee(), 6);
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(Balances::free_balance(1), 4);
+			assert_eq!(KittyModule::current_breed_fee(), 6);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::current_breed_fee(), 9);
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::InsufficientBalanceToBreed.into());
+		});
+	}
+
+	#[test]
+	fn breed_fee_saturates() {
+		with_externalities(&mut new_test_ext(), || {
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = u32::max_value());
+			assert_eq!(KittyModule::current_breed_fee(), u32::max_value());
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::current_breed_fee(), u32::max_value());
+		});
+	}
+
+	#[test]
+	fn is_related_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			for _ in 0..6 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+			}
+			// Siblings 6 and 7
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Cousins 8 and 9
+			assert_ok!(KittyModule::breed(Origin::signed(1), 6, 2));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 7, 3));
+			assert_eq!(KittyModule::parents(8), Some((6, 2)));
+			assert_eq!(KittyModule::parents(0), None);
+
+			// Verify
+			// Siblings
+			assert!(KittyModule::is_related(6, 7, 1));
+			assert!(!KittyModule::is_related(6, 7, 0));
+			// Parent and child
+			assert!(KittyModule::is_related(6, 8, 1));
+			assert!(KittyModule::is_related(0, 8, 2));
+			assert!(!KittyModule::is_related(0, 8, 1));
+			// Cousins
+			assert!(KittyModule::is_related(8, 9, 2));
+			assert!(!KittyModule::is_related(8, 9, 1));
+			// Unrelated
+			assert!(!KittyModule::is_related(4, 5, 5));
+			assert!(!KittyModule::is_related(8, 3, 5));
+			assert!(!KittyModule::is_related(8, 4, 5));
+
+			// Inbreeding is rejected when enabled
+			INBREEDING_DEPTH.with(|v| *v.borrow_mut() = 1);
+			assert_noop!(KittyModule::breed(Origin::signed(1), 6, 7), Error::KittiesRelated.into());
+			assert_ok!(KittyModule::breed(Origin::signed(1), 8, 9));
+		});
+	}
+
+	#[test]
+	fn fees_are_delivered_to_handler() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			CREATION_FEE.with(|v| *v.borrow_mut() = 2);
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 3);
+			assert_ok!(KittyModule::create(Origin::signed(3)));
+			assert_ok!(KittyModule::create(Origin::signed(3)));
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(3), 0, 1));
+			// Verify Storage
+			assert_eq!(FEES_PAID.with(|v| v.borrow().clone()), vec![2, 2, 6]);
+			assert_eq!(Balances::free_balance(3), 20);
+		});
+	}
+
+	#[test]
+	fn metadata_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::set_metadata(Origin::signed(2), 0, b"ipfs://a".to_vec()), Error::NotOwner.into());
+			assert_noop!(KittyModule::set_metadata(Origin::signed(1), 0, vec![0; MAX_METADATA_LENGTH + 1]), Error::MetadataTooLong.into());
+			// Call Functions
+			assert_ok!(KittyModule::set_metadata(Origin::signed(1), 0, vec![0; MAX_METADATA_LENGTH]));
+			assert_ok!(KittyModule::set_metadata(Origin::signed(1), 0, b"ipfs://a".to_vec()));
+			// Verify Storage
+			assert_eq!(KittyModule::metadata(0), Some(b"ipfs://a".to_vec()));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::MetadataSet(1, 0, b"ipfs://a".to_vec())));
+			// Overwrite
+			assert_ok!(KittyModule::set_metadata(Origin::signed(1), 0, b"ipfs://b".to_vec()));
+			assert_eq!(KittyModule::metadata(0), Some(b"ipfs://b".to_vec()));
+			// Clear
+			assert_noop!(KittyModule::clear_metadata(Origin::signed(2), 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::clear_metadata(Origin::signed(1), 0));
+			assert_eq!(KittyModule::metadata(0), None);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::MetadataCleared(1, 0)));
+		});
+	}
+
+	#[test]
+	fn ask_batch_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			LISTING_DEPOSIT.with(|v| *v.borrow_mut() = 2);
+			for _ in 0..3 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+			}
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Call Functions
+			assert_noop!(KittyModule::ask_batch(Origin::signed(1), vec![(0, 10), (1, 11), (2, 12), (3, 13)]), Error::NotOwner.into());
+			assert_noop!(KittyModule::ask_batch(Origin::signed(1), vec![(0, 10), (0, 11)]), Error::DuplicatedKitty.into());
+			assert_ok!(KittyModule::ask_batch(Origin::signed(1), vec![(0, 10), (1, 11), (2, 12)]));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), Some(10));
+			assert_eq!(KittyModule::kitty_price(1), Some(11));
+			assert_eq!(KittyModule::kitty_price(2), Some(12));
+			assert_eq!(KittyModule::for_sale(), vec![0, 1, 2]);
+			assert_eq!(Balances::reserved_balance(1), 6);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Ask(1, 2, 12)));
+		});
+	}
+
+	#[test]
+	fn destroy_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_noop!(KittyModule::destroy(Origin::signed(2), 0), Error::NotOwner.into());
+			// Call Functions
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 0));
+			// Verify Storage
+			assert!(!KittyModule::kitty_exists(0));
+			assert_eq!(KittyModule::kitty_owner(0), None);
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(KittyModule::owned_kitties_count(1), 0);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Destroyed(1, 0)));
+		});
+	}
+
+	#[test]
+	fn destroyed_ids_are_not_recycled_by_default() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 0));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::free_ids(), vec![]);
+			assert_eq!(KittyModule::kitties_count(), 3);
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![1, 2]);
+		});
+	}
+
+	#[test]
+	fn destroyed_ids_are_recycled() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			RECYCLE_IDS.with(|v| *v.borrow_mut() = true);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 0));
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 2));
+			assert_eq!(KittyModule::free_ids(), vec![0, 2]);
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			// Verify Storage
+			assert_eq!(KittyModule::free_ids(), vec![]);
+			assert_eq!(KittyModule::kitties_count(), 4);
+			assert_eq!(KittyModule::owned_kitties_iter(&2), vec![2, 0, 3]);
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+		});
+	}
+
+	#[test]
+	fn recycled_ids_do_not_inherit_relations() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			RECYCLE_IDS.with(|v| *v.borrow_mut() = true);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::propose_swap(Origin::signed(2), 4, 2));
+			// Call Functions
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 0));
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 2));
+			// Verify Storage
+			// Kitty 3 still names 0 as a parent so 0 is never reused
+			assert_eq!(KittyModule::free_ids(), vec![2]);
+			assert_eq!(KittyModule::parents(3), Some((0, 1)));
+			assert_eq!(KittyModule::swap_proposal(0), None);
+			assert_eq!(KittyModule::swap_proposals_of(4), vec![]);
+			// The new kitty 2 is unrelated to kitty 3 and has no pending swap
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(KittyModule::kitty_owner(2), Some(1));
+			assert_noop!(KittyModule::accept_swap(Origin::signed(1), 0), Error::SwapNotFound.into());
+			assert_ok!(KittyModule::breed(Origin::signed(1), 2, 3));
+			assert_eq!(KittyModule::parents(5), Some((2, 3)));
+			assert_eq!(KittyModule::children_of((2, 3)), vec![5]);
+		});
+	}
+
+	#[test]
+	fn reset_cooldown_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BASE_COOLDOWN.with(|v| *v.borrow_mut() = 5);
+			COOLDOWN_RESET_FEE.with(|v| *v.borrow_mut() = 2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_err!(KittyModule::breed(Origin::signed(1), 0, 1), Error::BreedCooldown.as_str());
+			assert_noop!(KittyModule::reset_cooldown(Origin::signed(2), 0), Error::NotOwner.into());
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, true));
+			assert_noop!(KittyModule::reset_cooldown(Origin::signed(1), 0), Error::AccountFrozen.into());
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, false));
+			// Call Functions
+			assert_ok!(KittyModule::reset_cooldown(Origin::signed(1), 0));
+			assert_ok!(KittyModule::reset_cooldown(Origin::signed(1), 1));
+			// Verify Storage
+			assert_eq!(KittyModule::cooldown_until(0), 0);
+			assert_eq!(Balances::free_balance(1), 6);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::CooldownReset(1, 1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+		});
+	}
+
+	#[test]
+	fn reset_cooldown_fails_if_not_cooling_down() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			BASE_COOLDOWN.with(|v| *v.borrow_mut() = 5);
+			COOLDOWN_RESET_FEE.with(|v| *v.borrow_mut() = 2);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::reset_cooldown(Origin::signed(1), 0), Error::NotOnCooldown.into());
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			System::set_block_number(5);
+			assert_noop!(KittyModule::reset_cooldown(Origin::signed(1), 0), Error::NotOnCooldown.into());
+			// Verify Storage
+			assert_eq!(Balances::free_balance(1), 10);
+		});
+	}
+
+	#[test]
+	fn combine_dna_works() {
+		// All zeros picks parent 2
+		assert_eq!(combine_dna(0b1010_1010, 0b0101_0011, 0b0000_0000), 0b0101_0011);
+		// All ones picks parent 1
+		assert_eq!(combine_dna(0b1010_1010, 0b0101_0011, 0b1111_1111), 0b1010_1010);
+		// Mixed selectors pick bit by bit
+		assert_eq!(combine_dna(0b1111_1111, 0b0000_0000, 0b1100_0101), 0b1100_0101);
+		assert_eq!(combine_dna(0b0000_0000, 0b1111_1111, 0b1100_0101), 0b0011_1010);
+		assert_eq!(combine_dna(0b1010_1010, 0b0101_0011, 0b1111_0000), 0b1010_0011);
+	}
+
+	#[test]
+	fn combine_parents_dna_works() {
+		assert_eq!(combine_parents_dna(&[0xaa, 0xbb], &[0x11, 0x22], &[0x00, 0xff]), vec![0x11, 0xbb]);
+		assert_eq!(combine_parents_dna(&[0xaa, 0xbb], &[0x11, 0x22], &[0xf0, 0x0f]), vec![0xa1, 0x2b]);
+		// Missing bytes of the shorter parent are zero
+		assert_eq!(combine_parents_dna(&[0xaa], &[0x11, 0x22], &[0xff, 0xff, 0x00]), vec![0xaa, 0x00, 0x00]);
+	}
+
+	#[test]
+	fn collection_changed_is_emitted() {
+		with_externalities(&mut new_test_ext(), || {
+			let collection_changed = |who| System::events().into_iter()
+				.filter(|record| record.event == TestEvent::kitties(RawEvent::CollectionChanged(who)))
+				.count();
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_eq!(collection_changed(1), 1);
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			// Verify
+			assert_eq!(collection_changed(1), 2);
+			assert_eq!(collection_changed(2), 1);
+		});
+	}
+
+	#[test]
+	fn transfer_refunds_offers() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::make_offer(Origin::signed(2), 0, 5));
+			assert_ok!(KittyModule::make_offer(Origin::signed(3), 0, 8));
+			assert_ok!(KittyModule::make_offer(Origin::signed(3), 0, 9));
+			assert_eq!(KittyModule::offerers(0), vec![2, 3]);
+			// Call Functions
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 4, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::offers(&(0, 2)), None);
+			assert_eq!(KittyModule::offers(&(0, 3)), None);
+			assert_eq!(KittyModule::offerers(0), vec![]);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(Balances::reserved_balance(3), 0);
+			assert_eq!(Balances::free_balance(3), 30);
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
-				prev: Some(2),
-				next: None,
-			}));
+	#[test]
+	fn breed_does_not_kill_account() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			<balances::ExistentialDeposit<Test>>::put(5);
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 3);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			// Fee of 6 would leave 4
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::InsufficientBalanceToBreed.into());
+			// Fee of 4 leaves 6
+			BREED_FEE_BASE.with(|v| *v.borrow_mut() = 2);
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(Balances::free_balance(1), 6);
 		});
 	}
 
 	#[test]
-	fn owned_kitties_can_remove_values() {
+	fn relist_works() {
 		with_externalities(&mut new_test_ext(), || {
-			OwnedKittiesList::<Test>::append(&0, 1);
-			OwnedKittiesList::<Test>::append(&0, 2);
-			OwnedKittiesList::<Test>::append(&0, 3);
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::relist(Origin::signed(1), 0, 12), Error::NotForSale.into());
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), Some(5)));
+			assert_noop!(KittyModule::relist(Origin::signed(2), 0, 12), Error::NotApproved.into());
+			// Call Functions
+			assert_ok!(KittyModule::relist(Origin::signed(1), 0, 12));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_price(0), Some(12));
+			assert_eq!(KittyModule::listing_expiry(0), Some(5));
+			assert_eq!(KittyModule::for_sale(), vec![0]);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::Relisted(1, 0, 12)));
+		});
+	}
 
-			OwnedKittiesList::<Test>::remove(&0, 2);
+	#[test]
+	fn frozen_account_cannot_trade() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 2, Some(5), None));
+			assert_noop!(KittyModule::set_frozen(Origin::signed(1), 1, true), "bad origin: expected to be a root origin");
+			// Call Functions
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, true));
+			// Verify
+			assert!(KittyModule::is_frozen(&1));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::AccountFrozen(1, true)));
+			assert_noop!(KittyModule::create(Origin::signed(1)), Error::AccountFrozen.into());
+			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), Error::AccountFrozen.into());
+			assert_noop!(KittyModule::transfer(Origin::signed(1), 3, 0), Error::AccountFrozen.into());
+			assert_noop!(KittyModule::ask(Origin::signed(1), 0, Some(5), None), Error::AccountFrozen.into());
+			assert_noop!(KittyModule::buy(Origin::signed(1), 2, 5), Error::AccountFrozen.into());
+			// Other accounts are unaffected
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::transfer(Origin::signed(2), 3, 3));
+			// Unfreeze
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, false));
+			assert!(!KittyModule::is_frozen(&1));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), None));
+			assert_ok!(KittyModule::buy(Origin::signed(1), 2, 5));
+			assert_ok!(KittyModule::transfer(Origin::signed(1), 3, 1));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: Some(3),
-				next: Some(1),
-			}));
+	#[test]
+	fn claim_faucet_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(5);
+			// Call Functions
+			assert_ok!(KittyModule::claim_faucet(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 1);
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::last_claim(1), Some(5));
+			assert_eq!(Balances::free_balance(1), 10);
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), Some(KittyLinkedItem::<Test> {
-				prev: None,
-				next: Some(3),
-			}));
+	#[test]
+	fn claim_faucet_again_fails() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(5);
+			assert_ok!(KittyModule::claim_faucet(Origin::signed(1)));
+			System::set_block_number(14);
+			// Call Functions
+			assert_noop!(KittyModule::claim_faucet(Origin::signed(1)), Error::FaucetCooldown.into());
+			// Other accounts are not limited
+			assert_ok!(KittyModule::claim_faucet(Origin::signed(2)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(KittyModule::last_claim(1), Some(5));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+	#[test]
+	fn claim_faucet_after_cooldown_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			System::set_block_number(5);
+			assert_ok!(KittyModule::claim_faucet(Origin::signed(1)));
+			System::set_block_number(15);
+			// Call Functions
+			assert_ok!(KittyModule::claim_faucet(Origin::signed(1)));
+			// Verify Storage
+			assert_eq!(KittyModule::kitties_count(), 2);
+			assert_eq!(KittyModule::kitty_owner(1), Some(1));
+			assert_eq!(KittyModule::last_claim(1), Some(15));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
-				prev: Some(1),
-				next: None,
-			}));
+	#[test]
+	fn breed_uses_breeding_strategy() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			AVERAGE_BREEDING.with(|v| *v.borrow_mut() = true);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			let dna1 = KittyModule::kitty(0).unwrap().dna;
+			let dna2 = KittyModule::kitty(1).unwrap().dna;
+			let average: Vec<u8> = dna1.iter().zip(&dna2).map(|(a, b)| ((*a as u16 + *b as u16) / 2) as u8).collect();
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			// Verify Storage
+			assert_eq!(BREEDINGS.with(|v| v.borrow().clone()), vec![(dna1, dna2)]);
+			assert_eq!(KittyModule::preview_breed(0, 1, vec![0; 16]), Some(average));
+		});
+	}
 
-			OwnedKittiesList::<Test>::remove(&0, 1);
+	#[test]
+	fn set_name_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(KittyModule::set_name(Origin::signed(2), 0, b"Tom".to_vec()), Error::NotOwner.into());
+			assert_noop!(KittyModule::set_name(Origin::signed(1), 0, vec![b'a'; MAX_NAME_LENGTH + 1]), Error::NameTooLong.into());
+			assert_noop!(KittyModule::set_name(Origin::signed(1), 0, vec![]), Error::EmptyName.into());
+			// Call Functions
+			assert_ok!(KittyModule::set_name(Origin::signed(1), 0, b"Tom".to_vec()));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_name(0), Some(b"Tom".to_vec()));
+			assert_eq!(KittyModule::name_owner(b"Tom".to_vec()), Some(0));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::NameSet(1, 0, b"Tom".to_vec())));
+			// Setting the same name again is allowed
+			assert_ok!(KittyModule::set_name(Origin::signed(1), 0, b"Tom".to_vec()));
+			assert_eq!(KittyModule::name_owner(b"Tom".to_vec()), Some(0));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: Some(3),
-				next: Some(3),
-			}));
+	#[test]
+	fn set_name_taken_fails() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::set_name(Origin::signed(1), 0, b"Tom".to_vec()));
+			// Call Functions
+			assert_noop!(KittyModule::set_name(Origin::signed(2), 1, b"Tom".to_vec()), Error::NameTaken.into());
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_name(1), None);
+			assert_eq!(KittyModule::name_owner(b"Tom".to_vec()), Some(0));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+	#[test]
+	fn rename_frees_old_name() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::set_name(Origin::signed(1), 0, b"Tom".to_vec()));
+			// Call Functions
+			assert_ok!(KittyModule::set_name(Origin::signed(1), 0, b"Jerry".to_vec()));
+			assert_ok!(KittyModule::set_name(Origin::signed(2), 1, b"Tom".to_vec()));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_name(0), Some(b"Jerry".to_vec()));
+			assert_eq!(KittyModule::kitty_name(1), Some(b"Tom".to_vec()));
+			assert_eq!(KittyModule::name_owner(b"Jerry".to_vec()), Some(0));
+			assert_eq!(KittyModule::name_owner(b"Tom".to_vec()), Some(1));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+	#[test]
+	fn force_transfer_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(5), None));
+			assert_ok!(KittyModule::set_lock(Origin::signed(1), 0, true));
+			assert_noop!(KittyModule::force_transfer(Origin::ROOT, 2, 3, 0), Error::NotOwner.into());
+			// Call Functions
+			assert_ok!(KittyModule::force_transfer(Origin::ROOT, 1, 2, 0));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(2));
+			assert_eq!(KittyModule::kitty_price(0), None);
+			assert!(!KittyModule::is_locked(0));
+			assert_eq!(KittyModule::owned_kitties_count(1), 0);
+			assert_eq!(KittyModule::owned_kitties_count(2), 1);
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::ForceTransferred(1, 2, 0)));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(3))), Some(KittyLinkedItem::<Test> {
-				prev: None,
-				next: None,
-			}));
+	#[test]
+	fn force_transfer_requires_root() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			// Call Functions
+			assert_noop!(KittyModule::force_transfer(Origin::signed(1), 1, 2, 0), "bad origin: expected to be a root origin");
+			assert_noop!(KittyModule::force_transfer(Origin::signed(2), 1, 2, 0), "bad origin: expected to be a root origin");
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+		});
+	}
 
-			OwnedKittiesList::<Test>::remove(&0, 3);
+	#[test]
+	fn siblings_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			for _ in 0..3 {
+				assert_ok!(KittyModule::create(Origin::signed(1)));
+			}
+			// Call Functions
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 1, 0));
+			// Half sibling
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 2));
+			// Verify Storage
+			assert_eq!(KittyModule::children_of((0, 1)), vec![3, 4, 5]);
+			assert_eq!(KittyModule::children_of((0, 2)), vec![6]);
+			assert_eq!(KittyModule::siblings(3), vec![4, 5]);
+			assert_eq!(KittyModule::siblings(5), vec![3, 4]);
+			assert_eq!(KittyModule::siblings(6), Vec::<u32>::new());
+			assert_eq!(KittyModule::siblings(0), Vec::<u32>::new());
+			// Destroyed kitties are not siblings
+			assert_ok!(KittyModule::destroy(Origin::signed(1), 4));
+			assert_eq!(KittyModule::siblings(3), vec![5]);
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, None)), Some(KittyLinkedItem::<Test> {
-				prev: None,
-				next: None,
-			}));
+	#[test]
+	fn transfer_with_memo_works() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_noop!(
+				KittyModule::transfer_with_memo(Origin::signed(1), 2, 0, vec![0; MAX_MEMO_LENGTH + 1]),
+				Error::MemoTooLong.into()
+			);
+			assert_noop!(KittyModule::transfer_with_memo(Origin::signed(2), 3, 0, b"deposit 42".to_vec()), Error::NotOwner.into());
+			// Call Functions
+			assert_ok!(KittyModule::transfer_with_memo(Origin::signed(1), 2, 0, b"deposit 42".to_vec()));
+			assert_ok!(KittyModule::transfer_with_memo(Origin::signed(2), 3, 0, vec![0; MAX_MEMO_LENGTH]));
+			// Verify Storage
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
+			let events: Vec<_> = System::events().into_iter().map(|record| record.event).collect();
+			assert!(events.contains(&TestEvent::kitties(RawEvent::TransferredWithMemo(1, 2, 0, b"deposit 42".to_vec()))));
+			assert_eq!(last_event(), TestEvent::kitties(RawEvent::TransferredWithMemo(2, 3, 0, vec![0; MAX_MEMO_LENGTH])));
+		});
+	}
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(1))), None);
+	#[test]
+	fn can_buy_matches_buy() {
+		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(15), None));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+			// Verify
+			let cases = vec![
+				(2, 2, 15, Error::InvalidKitty),
+				(2, 1, 15, Error::NotForSale),
+				(1, 0, 15, Error::BuyOwnKitty),
+				(2, 0, 14, Error::PriceTooLow),
+			];
+			for (buyer, kitty_id, price, error) in cases {
+				assert_eq!(KittyModule::can_buy(&buyer, kitty_id, price), Err(error.as_str()));
+				assert_noop!(KittyModule::buy(Origin::signed(buyer), kitty_id, price), error.into());
+			}
+			// Account 3 has 30 but account 2 only has 20
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(25), None));
+			assert_eq!(KittyModule::can_buy(&2, 0, 25), Err(Error::InsufficientBalance.as_str()));
+			assert_noop!(KittyModule::buy(Origin::signed(2), 0, 25), Error::InsufficientBalance.into());
+			assert_ok!(KittyModule::set_paused(Origin::ROOT, true));
+			assert_eq!(KittyModule::can_buy(&3, 0, 25), Err(Error::Paused.as_str()));
+			assert_noop!(KittyModule::buy(Origin::signed(3), 0, 25), Error::Paused.into());
+			assert_ok!(KittyModule::set_paused(Origin::ROOT, false));
 
-			assert_eq!(OwnedKittiesTest::get(&(0, Some(2))), None);
+			// Success leaves storage untouched
+			assert_ok!(KittyModule::can_buy(&3, 0, 25));
+			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 25));
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
 		});
 	}
 
 	#[test]
-	fn basic_setup_works() {
-		with_externalities(&mut new_test_ext(), || {
-			// Verify Initial Storage
-			assert_eq!(KittyModule::kitties_count(), 0);
-			assert!(KittyModule::kitty(0).is_none());
-			assert_eq!(KittyModule::kitty_owner(0), None);
-			assert_eq!(KittyModule::kitty_price(0), None);
-			assert_eq!(Balances::free_balance(1), 10);
-			assert_eq!(Balances::free_balance(2), 20);
-		});
+	fn weight_selector_works() {
+		// Equal rarity keeps the selector
+		assert_eq!(weight_selector(0b1010_0001, 5, 5), 0b1010_0001);
+		// Rarer first parent sets more bits
+		assert_eq!(weight_selector(0b1010_0001, 6, 5), 0b1110_0011);
+		// Rarer second parent clears more bits
+		assert_eq!(weight_selector(0b1010_0001, 5, 6), 0b0000_0001);
+		assert_eq!(weight_selector(0b1100_0011, 5, 6), 0b1000_0011);
 	}
 
 	#[test]
-	fn create_works() {
+	fn breed_favours_rarer_parent() {
 		with_externalities(&mut new_test_ext(), || {
+			// Setup
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0xff; 16], 0), Ok(0));
+			assert_eq!(KittyModule::insert_kitty(&1, vec![0x00; 16], 0), Ok(1));
 			// Call Functions
-			assert_ok!(KittyModule::create(Origin::signed(1)));
+			for _ in 0..20 {
+				assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+				assert_ok!(KittyModule::breed(Origin::signed(1), 1, 0));
+			}
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 1);
-			assert!(KittyModule::kitty(0).is_some());
-			assert_eq!(KittyModule::kitty_owner(0), Some(1));
-			assert_eq!(KittyModule::kitty_price(0), None);
+			let total: u32 = (2..42).map(|kitty_id| dna_rarity(&KittyModule::kitty(kitty_id).unwrap().dna)).sum();
+			// Unweighted breeding averages half of the rarity of the rare parent
+			assert!(total / 40 > dna_rarity(&[0xff; 16]) * 5 / 8);
 		});
 	}
 
 	#[test]
-	fn create_handles_basic_errors() {
+	fn buy_pays_no_transfer_fees() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
-			<KittiesCount<Test>>::put(u32::max_value());
+			<balances::TransferFee<Test>>::put(3);
+			<balances::CreationFee<Test>>::put(3);
+			MARKETPLACE_FEE_PERCENT.with(|v| *v.borrow_mut() = 10);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(20), None));
+			assert_ok!(KittyModule::ask(Origin::signed(1), 1, Some(10), None));
+			let total_issuance = Balances::total_issuance();
 			// Call Functions
-			assert_noop!(KittyModule::create(Origin::signed(1)), "Kitties count overflow");
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 20));
+			// Account 3 can pay the price without the fees it would take to transfer it
+			assert_ok!(KittyModule::buy(Origin::signed(3), 1, 10));
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), u32::max_value());
-			assert!(KittyModule::kitty(0).is_none());
-			assert_eq!(KittyModule::kitty_owner(0), None);
-			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(Balances::free_balance(3), 0);
+			assert_eq!(Balances::free_balance(99), 3);
+			assert_eq!(Balances::free_balance(1), 37);
+			assert_eq!(Balances::total_issuance(), total_issuance);
 		});
 	}
 
 	#[test]
-	fn transfer_works() {
+	fn buy_pays_nobody_if_royalty_cannot_be_paid() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
+			<balances::ExistentialDeposit<Test>>::put(5);
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 10);
 			assert_ok!(KittyModule::create(Origin::signed(1)));
-			// Call Functions
 			assert_ok!(KittyModule::transfer(Origin::signed(1), 2, 0));
+			assert_ok!(KittyModule::ask(Origin::signed(2), 0, Some(20), None));
+			// The breeder account is gone so a royalty of 2 cannot create it
+			Balances::make_free_balance_be(&1, 0);
+			// Call Functions
+			assert_noop!(KittyModule::buy(Origin::signed(3), 0, 20), Error::PaymentBelowMinimum.into());
+			assert_eq!(KittyModule::can_buy(&3, 0, 20), Err(Error::PaymentBelowMinimum.as_str()));
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 1);
-			assert!(KittyModule::kitty(0).is_some());
 			assert_eq!(KittyModule::kitty_owner(0), Some(2));
-			assert_eq!(KittyModule::kitty_price(0), None);
+			assert_eq!(Balances::free_balance(2), 20);
+			assert_eq!(Balances::free_balance(3), 30);
+			// The whole price is split once the royalty can be paid
+			ROYALTY_PERCENT.with(|v| *v.borrow_mut() = 25);
+			assert_ok!(KittyModule::buy(Origin::signed(3), 0, 20));
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::free_balance(2), 35);
+			assert_eq!(Balances::free_balance(3), 10);
 		});
 	}
 
 	#[test]
-	fn transfer_handles_basic_errors() {
+	fn migrate_rebuilds_owned_counts_and_breeders() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
-			assert_ok!(KittyModule::create(Origin::signed(1)));
+			support::storage::put(&<Kitties<Test>>::key_for(0), &[1u8; 16]);
+			support::storage::put(&<Kitties<Test>>::key_for(1), &[2u8; 16]);
+			support::storage::put(&<Kitties<Test>>::key_for(2), &[3u8; 16]);
+			<KittiesCount<Test>>::put(3);
+			// The old layout has owners and owned lists but no counts or breeders
+			<OwnedKittiesList<Test>>::append(&1, 0);
+			<OwnedKittiesList<Test>>::append(&2, 1);
+			<OwnedKittiesList<Test>>::append(&1, 2);
+			<OwnedKittiesCount<Test>>::remove(1);
+			<OwnedKittiesCount<Test>>::remove(2);
+			<KittyOwners<Test>>::insert(0, 1);
+			<KittyOwners<Test>>::insert(1, 2);
+			<KittyOwners<Test>>::insert(2, 1);
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![]);
 			// Call Functions
-			assert_noop!(KittyModule::transfer(Origin::signed(2), 2, 0), "Only owner can transfer kitty");
-			assert_noop!(KittyModule::transfer(Origin::signed(1), 2, 1), "Only owner can transfer kitty");
+			KittyModule::on_initialize(1);
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 1);
-			assert!(KittyModule::kitty(0).is_some());
-			assert_eq!(KittyModule::kitty_owner(0), Some(1));
+			assert_eq!(KittyModule::owned_kitties_count(1), 2);
+			assert_eq!(KittyModule::owned_kitties_count(2), 1);
+			assert_eq!(KittyModule::owned_kitties_iter(&1), vec![0, 2]);
+			assert_eq!(KittyModule::owned_kitties_iter(&2), vec![1]);
+			assert_eq!(KittyModule::breeder(0), 1);
+			assert_eq!(KittyModule::breeder(1), 2);
+			assert_eq!(KittyModule::breeder(2), 1);
+			// Listings can be removed again
+			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10), None));
+			assert_ok!(KittyModule::delist_all(Origin::signed(1)));
 			assert_eq!(KittyModule::kitty_price(0), None);
 		});
 	}
 
 	#[test]
-	fn breed_works() {
+	fn migrate_in_batches() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
-			assert_ok!(KittyModule::create(Origin::signed(1)));
-			assert_ok!(KittyModule::create(Origin::signed(1)));
+			let count = MAX_MIGRATED_KITTIES_PER_BLOCK + 5;
+			for i in 0..count {
+				support::storage::put(&<Kitties<Test>>::key_for(i as u32), &[1u8; 16]);
+				<OwnedKittiesList<Test>>::append(&1, i as u32);
+				<KittyOwners<Test>>::insert(i as u32, 1);
+			}
+			<OwnedKittiesCount<Test>>::remove(1);
+			<KittiesCount<Test>>::put(count as u32);
 			// Call Functions
-			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			KittyModule::on_initialize(1);
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 3);
+			assert_eq!(KittyModule::storage_version(), 0);
+			assert_eq!(KittyModule::migration_cursor(), Some((MAX_MIGRATED_KITTIES_PER_BLOCK, count, false)));
 			assert!(KittyModule::kitty(0).is_some());
-			assert!(KittyModule::kitty(1).is_some());
-			assert!(KittyModule::kitty(2).is_some());
-			assert_eq!(KittyModule::kitty_owner(0), Some(1));
-			assert_eq!(KittyModule::kitty_owner(1), Some(1));
-			assert_eq!(KittyModule::kitty_owner(2), Some(1));
-			assert_eq!(KittyModule::kitty_price(0), None);
-			assert_eq!(KittyModule::kitty_price(1), None);
-			assert_eq!(KittyModule::kitty_price(2), None);
+			assert!(KittyModule::kitty(count as u32 - 1).is_none());
+			// The module is paused until the migration is done
+			assert_noop!(KittyModule::create(Origin::signed(2)), Error::Paused.into());
+			assert_noop!(KittyModule::set_paused(Origin::ROOT, false), Error::MigrationInProgress.into());
+			KittyModule::on_initialize(2);
+			assert_eq!(KittyModule::storage_version(), STORAGE_VERSION);
+			assert_eq!(KittyModule::migration_cursor(), None);
+			assert!(!KittyModule::paused());
+			assert!(KittyModule::kitty(count as u32 - 1).is_some());
+			assert_eq!(KittyModule::owned_kitties_count(1), count);
+			assert_ok!(KittyModule::create(Origin::signed(2)));
 		});
 	}
 
 	#[test]
-	fn breed_handles_basic_errors() {
+	fn frozen_account_cannot_trade_indirectly() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
+			System::set_block_number(1);
+			assert_ok!(KittyModule::create(Origin::signed(1)));
 			assert_ok!(KittyModule::create(Origin::signed(1)));
 			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::create(Origin::signed(2)));
+			assert_ok!(KittyModule::set_approval_for_all(Origin::signed(1), 2, true));
+			assert_ok!(KittyModule::start_auction(Origin::signed(1), 1, 5, 5, 10));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(2), 3, 5, 10));
+			assert_ok!(KittyModule::make_offer(Origin::signed(3), 0, 5));
+			assert_ok!(KittyModule::make_offer(Origin::signed(1), 2, 5));
+			assert_ok!(KittyModule::propose_swap(Origin::signed(2), 2, 0));
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 1, true));
 			// Call Functions
-			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 0), "Needs different parent");
-			assert_noop!(KittyModule::breed(Origin::signed(2), 0, 1), "Not owner of kitty1");
-			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 1), "Not owner of kitty2");
-			assert_noop!(KittyModule::breed(Origin::signed(1), 2, 1), "Invalid kitty_id_1");
-			assert_noop!(KittyModule::breed(Origin::signed(1), 0, 2), "Invalid kitty_id_2");
+			let frozen = Error::AccountFrozen.as_str();
+			assert_noop!(KittyModule::transfer_from(Origin::signed(2), 1, 3, 0), frozen);
+			assert_noop!(KittyModule::gift(Origin::signed(2), 1, 2), frozen);
+			assert_noop!(KittyModule::bid_auction(Origin::signed(2), 1, 5), frozen);
+			assert_noop!(KittyModule::place_bid(Origin::signed(1), 3, 5), frozen);
+			assert_noop!(KittyModule::make_offer(Origin::signed(1), 3, 5), frozen);
+			assert_noop!(KittyModule::accept_offer(Origin::signed(1), 0, 3), frozen);
+			assert_noop!(KittyModule::accept_offer(Origin::signed(2), 2, 1), frozen);
+			assert_noop!(KittyModule::propose_swap(Origin::signed(1), 0, 2), frozen);
+			assert_noop!(KittyModule::accept_swap(Origin::signed(1), 0), frozen);
+			assert_noop!(KittyModule::abandon(Origin::signed(1), 0), frozen);
+			assert_noop!(KittyModule::ask(Origin::signed(2), 0, Some(5), None), frozen);
+			assert_noop!(KittyModule::offer_sire(Origin::signed(1), 0, Some(5)), frozen);
+			assert_noop!(KittyModule::start_auction(Origin::signed(1), 0, 5, 5, 10), frozen);
+			assert_noop!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10), frozen);
+			assert_noop!(KittyModule::destroy(Origin::signed(1), 0), frozen);
+			assert_noop!(KittyModule::approve(Origin::signed(1), 3, 0), frozen);
+			assert_noop!(KittyModule::approve(Origin::signed(2), 3, 0), frozen);
+			assert_noop!(KittyModule::set_approval_for_all(Origin::signed(1), 3, true), frozen);
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 2);
-			assert!(KittyModule::kitty(0).is_some());
-			assert!(KittyModule::kitty(1).is_some());
-			assert!(KittyModule::kitty(2).is_none());
 			assert_eq!(KittyModule::kitty_owner(0), Some(1));
-			assert_eq!(KittyModule::kitty_owner(1), Some(2));
-			assert_eq!(KittyModule::kitty_price(0), None);
-			assert_eq!(KittyModule::kitty_price(1), None);
+			assert_eq!(KittyModule::kitty_owner(2), Some(2));
+			assert!(KittyModule::swap_proposal(0).is_some());
+			// Root can still move kitties of a frozen account
+			assert_ok!(KittyModule::force_transfer(Origin::ROOT, 1, 3, 0));
+			assert_eq!(KittyModule::kitty_owner(0), Some(3));
 		});
 	}
 
 	#[test]
-	fn ask_works() {
+	fn english_auction_refunds_frozen_bidder() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
+			System::set_block_number(1);
 			assert_ok!(KittyModule::create(Origin::signed(1)));
+			assert_ok!(KittyModule::start_english_auction(Origin::signed(1), 0, 5, 10));
+			assert_ok!(KittyModule::place_bid(Origin::signed(2), 0, 10));
+			assert_ok!(KittyModule::set_frozen(Origin::ROOT, 2, true));
 			// Call Functions
-			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10)));
+			System::set_block_number(10);
+			KittyModule::on_finalize(10);
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 1);
-			assert!(KittyModule::kitty(0).is_some());
 			assert_eq!(KittyModule::kitty_owner(0), Some(1));
-			assert_eq!(KittyModule::kitty_price(0), Some(10));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(2), 0);
+			assert_eq!(Balances::free_balance(2), 20);
 		});
 	}
 
 	#[test]
-	fn buy_works() {
+	fn breed_fixes_dna_length_of_breeding_strategy() {
 		with_externalities(&mut new_test_ext(), || {
 			// Setup
 			assert_ok!(KittyModule::create(Origin::signed(1)));
-			assert_ok!(KittyModule::ask(Origin::signed(1), 0, Some(10)));
+			assert_ok!(KittyModule::create(Origin::signed(1)));
 			// Call Functions
-			assert_ok!(KittyModule::buy(Origin::signed(2), 0, 10));
+			BREEDING_LENGTH.with(|v| *v.borrow_mut() = Some(40));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			assert_eq!(KittyModule::preview_breed(0, 1, vec![0; 16]), Some(vec![0xff; 16]));
+			BREEDING_LENGTH.with(|v| *v.borrow_mut() = Some(4));
+			assert_ok!(KittyModule::breed(Origin::signed(1), 0, 1));
+			let mut padded = vec![0xff; 4];
+			padded.resize(16, 0);
+			assert_eq!(KittyModule::preview_breed(0, 1, vec![0; 16]), Some(padded));
 			// Verify Storage
-			assert_eq!(KittyModule::kitties_count(), 1);
-			assert!(KittyModule::kitty(0).is_some());
-			assert_eq!(KittyModule::kitty_owner(0), Some(2));
-			assert_eq!(KittyModule::kitty_price(0), None);
-			assert_eq!(Balances::free_balance(1), 20);
-			assert_eq!(Balances::free_balance(2), 10);
+			assert_eq!(KittyModule::kitty(2).unwrap().dna.len(), 16);
+			assert_eq!(KittyModule::kitty(3).unwrap().dna.len(), 16);
 		});
 	}
 }
-
@@ -17,7 +17,7 @@ use runtime_primitives::{
 };
 use client::{
 	block_builder::api::{CheckInherentsResult, InherentData, self as block_builder_api},
-	runtime_api, impl_runtime_apis
+	runtime_api, impl_runtime_apis, decl_runtime_apis
 };
 use version::RuntimeVersion;
 #[cfg(feature = "std")]
@@ -31,7 +31,7 @@ pub use timestamp::Call as TimestampCall;
 pub use balances::Call as BalancesCall;
 pub use runtime_primitives::{Permill, Perbill};
 pub use timestamp::BlockPeriod;
-pub use support::{StorageValue, construct_runtime};
+pub use support::{StorageValue, construct_runtime, parameter_types, traits::Get};
 
 /// The type that is used for identifying authorities.
 pub type AuthorityId = <AuthoritySignature as Verify>::Signer;
@@ -54,8 +54,15 @@ pub type BlockNumber = u64;
 /// Index of an account's extrinsic in the chain.
 pub type Nonce = u64;
 
+/// Balance of an account.
+pub type Balance = u128;
+
+/// Index of a kitty.
+pub type KittyIndex = u32;
+
 /// Used for the module kitties in `./kitties.rs`
 mod kitties;
+pub use kitties::{KittyDetails, KittyGender};
 
 mod linked_item;
 
@@ -169,7 +176,7 @@ impl timestamp::Trait for Runtime {
 
 impl balances::Trait for Runtime {
 	/// The type for recording an account's balance.
-	type Balance = u128;
+	type Balance = Balance;
 	/// What to do if an account's free balance gets zeroed.
 	type OnFreeBalanceZero = ();
 	/// What to do if a new account is created.
@@ -188,10 +195,77 @@ impl sudo::Trait for Runtime {
 	type Proposal = Call;
 }
 
+parameter_types! {
+	pub const MaxBatchSize: u32 = 20;
+	pub const MaxCreateBatchSize: u32 = 10;
+	pub const MutationRate: u8 = 8;
+	pub const CreationFee: u128 = 1_000;
+	pub const BreedFeeBase: u128 = 1_000;
+	pub const BreedFeeStep: u32 = 1_000;
+	pub const RoyaltyPercent: u32 = 5;
+	pub const ListingDeposit: u128 = 1_000;
+	pub const MinListingPrice: u128 = 500;
+	pub const DnaLength: u32 = 16;
+	pub const MaxKitties: u32 = 1_000_000;
+	pub const MaxAuctionsSettledPerBlock: u32 = 50;
+	pub const MarketplaceFeePercent: u32 = 2;
+	pub const BreedingCharges: u32 = 10;
+	pub const TwinChance: u8 = 2;
+	pub const BaseCooldown: BlockNumber = 10;
+	pub const CooldownResetFee: u128 = 1_000;
+	pub const MaxGeneration: u32 = 64;
+	pub const InbreedingDepth: u32 = 0;
+	pub const RecycleIds: bool = false;
+	pub const FaucetCooldown: BlockNumber = 1_000;
+}
+
+/// Marketplace fees are paid to the sudo key.
+pub struct FeeCollector;
+impl support::traits::Get<AccountId> for FeeCollector {
+	fn get() -> AccountId {
+		Sudo::key()
+	}
+}
+
+/// Abandoned kitties are owned by the all zero account.
+pub struct NullAccount;
+impl support::traits::Get<AccountId> for NullAccount {
+	fn get() -> AccountId {
+		AccountId::default()
+	}
+}
+
 impl kitties::Trait for Runtime {
 	type Event = Event;
-	type KittyIndex = u32;
+	type KittyIndex = KittyIndex;
 	type Currency = Balances;
+	type MarketCurrency = Balances;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxCreateBatchSize = MaxCreateBatchSize;
+	type MutationRate = MutationRate;
+	type CreationFee = CreationFee;
+	type BreedFeeBase = BreedFeeBase;
+	type BreedFeeStep = BreedFeeStep;
+	type OnFeePaid = ();
+	type RoyaltyPercent = RoyaltyPercent;
+	type ListingDeposit = ListingDeposit;
+	type MinListingPrice = MinListingPrice;
+	type DnaLength = DnaLength;
+	type MaxKitties = MaxKitties;
+	type MaxAuctionsSettledPerBlock = MaxAuctionsSettledPerBlock;
+	type MarketplaceFeePercent = MarketplaceFeePercent;
+	type FeeCollector = FeeCollector;
+	type BreedingCharges = BreedingCharges;
+	type TwinChance = TwinChance;
+	type BaseCooldown = BaseCooldown;
+	type CooldownResetFee = CooldownResetFee;
+	type MaxGeneration = MaxGeneration;
+	type InbreedingDepth = InbreedingDepth;
+	type RecycleIds = RecycleIds;
+	type FaucetCooldown = FaucetCooldown;
+	type NullAccount = NullAccount;
+	type BreedingStrategy = ();
+	type OnKittyTransfer = ();
 }
 
 construct_runtime!(
@@ -208,7 +282,7 @@ construct_runtime!(
 		Balances: balances,
 		Sudo: sudo,
 		// Substrate Kitties module
-		Kitties: kitties::{Module, Storage, Call, Event<T>},
+		Kitties: kitties::{Module, Storage, Call, Event<T>, Config<T>},
 	}
 );
 
@@ -229,6 +303,14 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Nonce, Call>;
 /// Executive: handles dispatch to the various modules.
 pub type Executive = executive::Executive<Runtime, Block, Context, Balances, AllModules>;
 
+decl_runtime_apis! {
+	/// The API to query kitties.
+	pub trait KittyApi {
+		/// Get the full details of a kitty.
+		fn kitty_details(kitty_id: KittyIndex) -> Option<KittyDetails<AccountId, Balance>>;
+	}
+}
+
 // Implement our runtime API endpoints. This is just a bunch of proxying.
 impl_runtime_apis! {
 	impl runtime_api::Core<Block> for Runtime {
@@ -300,4 +382,50 @@ impl_runtime_apis! {
 			Consensus::authorities()
 		}
 	}
+
+	impl self::KittyApi<Block> for Runtime {
+		fn kitty_details(kitty_id: KittyIndex) -> Option<KittyDetails<AccountId, Balance>> {
+			Kitties::kitty_details(kitty_id)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use runtime_io::with_externalities;
+	use primitives::Blake2Hasher;
+
+	fn new_test_ext(kitties: Vec<(AccountId, Vec<u8>)>) -> runtime_io::TestExternalities<Blake2Hasher> {
+		GenesisConfig {
+			consensus: None,
+			system: None,
+			timestamp: None,
+			indices: None,
+			balances: None,
+			sudo: None,
+			kitties: Some(KittiesConfig { kitties }),
+		}.build_storage().unwrap().0.into()
+	}
+
+	/// Call `KittyApi::kitty_details` the way the client does, through the runtime api dispatch
+	fn kitty_details(kitty_id: KittyIndex) -> Option<KittyDetails<AccountId, Balance>> {
+		let result = api::dispatch("KittyApi_kitty_details", &kitty_id.encode()).expect("KittyApi is implemented");
+		Decode::decode(&mut &result[..]).expect("Result is a valid encoding")
+	}
+
+	#[test]
+	fn kitty_details_works_through_runtime_api() {
+		let owner = AccountId::from_raw([1; 32]);
+		with_externalities(&mut new_test_ext(vec![(owner.clone(), vec![0x12; 16])]), || {
+			assert_eq!(kitty_details(0), Some(KittyDetails {
+				dna: vec![0x12; 16],
+				owner,
+				price: None,
+				generation: 0,
+				gender: KittyGender::Male,
+			}));
+			assert_eq!(kitty_details(1), None);
+		});
+	}
 }
@@ -1,6 +1,7 @@
 use support::{StorageMap, Parameter};
 use runtime_primitives::traits::Member;
 use parity_codec::{Encode, Decode};
+use rstd::prelude::*;
 
 #[cfg_attr(feature = "std", derive(Debug, PartialEq, Eq))]
 #[derive(Encode, Decode)]
@@ -9,12 +10,13 @@ pub struct LinkedItem<Item> {
 	pub next: Option<Item>,
 }
 
-pub struct LinkedList<Storage, Key, Item>(rstd::marker::PhantomData<(Storage, Key, Item)>);
+pub struct LinkedList<Storage, Count, Key, Item>(rstd::marker::PhantomData<(Storage, Count, Key, Item)>);
 
-impl<Storage, Key, Value> LinkedList<Storage, Key, Value> where
+impl<Storage, Count, Key, Value> LinkedList<Storage, Count, Key, Value> where
   Value: Parameter + Member + Copy,
   Key: Parameter,
   Storage: StorageMap<(Key, Option<Value>), LinkedItem<Value>, Query = Option<LinkedItem<Value>>>,
+  Count: StorageMap<Key, u64, Query = u64>,
 {
 	fn read_head(key: &Key) -> LinkedItem<Value> {
 		Self::read(key, None)
@@ -35,6 +37,53 @@ impl<Storage, Key, Value> LinkedList<Storage, Key, Value> where
 		Storage::insert(&(key.clone(), value), item);
 	}
 
+	pub fn len(key: &Key) -> u64 {
+		Count::get(key)
+	}
+
+	/// Whether the value is in the list
+	pub fn contains(key: &Key, value: Value) -> bool {
+		Storage::exists(&(key.clone(), Some(value)))
+	}
+
+	/// Zero based position of the value from head, None if not in the list
+	pub fn position(key: &Key, value: Value) -> Option<u32> {
+		if !Self::contains(key, value) {
+			return None;
+		}
+		Self::items(key).iter().position(|item| *item == value).map(|pos| pos as u32)
+	}
+
+	/// All the values in the list, from head to tail
+	pub fn items(key: &Key) -> Vec<Value> {
+		let len = Self::len(key);
+		let mut items = Vec::new();
+		let mut next = Self::read_head(key).next;
+		while let Some(value) = next {
+			// Bound by the length to guard against a corrupted list
+			if items.len() as u64 >= len {
+				break;
+			}
+			items.push(value);
+			next = Self::read(key, Some(value)).next;
+		}
+		items
+	}
+
+	/// At most `limit` values following `start`, or from head if `start` is None
+	pub fn page(key: &Key, start: Option<Value>, limit: u32) -> Vec<Value> {
+		let mut items = Vec::new();
+		let mut next = Self::read(key, start).next;
+		while let Some(value) = next {
+			if items.len() as u32 >= limit {
+				break;
+			}
+			items.push(value);
+			next = Self::read(key, Some(value)).next;
+		}
+		items
+	}
+
 	pub fn append(key: &Key, value: Value) {
 		let head = Self::read_head(key);
 		let new_head = LinkedItem {
@@ -56,11 +105,16 @@ impl<Storage, Key, Value> LinkedList<Storage, Key, Value> where
 			next: None,
 		};
 		Self::write(key, Some(value), item);
+
+		Count::mutate(key, |count| *count += 1);
 	}
 
+	/// Remove the value from the list. No-op if the value is not in the list of `key`.
 	pub fn remove(key: &Key, value: Value) {
+		// Only the node stored under this key is unlinked, never nodes of another list
 		if let Some(item) = Storage::take(&(key.clone(), Some(value))) {
 			let prev = Self::read(key, item.prev);
+			debug_assert!(prev.next == Some(value), "Linked list is corrupted");
 			let new_prev = LinkedItem {
 				prev: prev.prev,
 				next: item.next,
@@ -69,12 +123,15 @@ impl<Storage, Key, Value> LinkedList<Storage, Key, Value> where
 			Self::write(key, item.prev, new_prev);
 
 			let next = Self::read(key, item.next);
+			debug_assert!(next.prev == Some(value), "Linked list is corrupted");
 			let new_next = LinkedItem {
 				prev: item.prev,
 				next: next.next,
 			};
 
 			Self::write(key, item.next, new_next);
+
+			Count::mutate(key, |count| *count = count.saturating_sub(1));
 		}
 	}
 }
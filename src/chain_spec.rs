@@ -1,9 +1,11 @@
 use hex_literal::hex;
-use primitives::{crypto::UncheckedInto, ed25519, sr25519, Pair};
+use parity_codec::Encode;
+use primitives::{blake2_256, crypto::UncheckedInto, ed25519, sr25519, Pair};
 use substrate_kitties_runtime::{
-    AccountId, BalancesConfig, ConsensusConfig, GenesisConfig, IndicesConfig, SudoConfig,
-    TimestampConfig,
+    AccountId, BalancesConfig, ConsensusConfig, DnaLength, GenesisConfig, Get, IndicesConfig,
+    KittiesConfig, SudoConfig, TimestampConfig,
 };
+use std::path::{Path, PathBuf};
 use substrate_service;
 use substrate_telemetry::TelemetryEndpoints;
 
@@ -24,8 +26,14 @@ pub enum Alternative {
     Development,
     /// Whatever the current runtime is, with simple Alice/Bob auths.
     LocalTestnet,
+    /// Local testnet with the given number of extra endowed accounts `//Account0`, `//Account1`, ...
+    LocalTestnetWithAccounts(u32),
+    /// Local testnet with explicit balances of named accounts, e.g. `local-endowments:Alice=1000,Bob=10`
+    LocalTestnetWithEndowments(Vec<(AccountId, u128)>),
     DemoTestnet,
     DemoTestnetLatest,
+    /// Demo testnet loaded from a chain spec file at runtime, to change it without rebuilding.
+    DemoFromFile(PathBuf),
 }
 
 fn authority_key(s: &str) -> AuthorityId {
@@ -40,6 +48,16 @@ fn account_key(s: &str) -> AccountId {
         .public()
 }
 
+/// Alice, Bob, Charlie, Dave, Eve and Ferdie
+fn well_known_accounts() -> Vec<AccountId> {
+    ["Alice", "Bob", "Charlie", "Dave", "Eve", "Ferdie"].iter().map(|s| account_key(s)).collect()
+}
+
+/// Deterministic accounts `//Account0` to `//Account{count - 1}`
+fn numbered_accounts(count: u32) -> Vec<AccountId> {
+    (0..count).map(|i| account_key(&format!("Account{}", i))).collect()
+}
+
 impl Alternative {
     /// Get an actual chain config from one of the alternatives.
     pub(crate) fn load(self) -> Result<ChainSpec, String> {
@@ -50,15 +68,11 @@ impl Alternative {
                 || {
                     testnet_genesis(
                         vec![authority_key("Alice")],
-                        vec![
-                            account_key("Alice"),
-                            account_key("Bob"),
-                            account_key("Charlie"),
-                            account_key("Dave"),
-                            account_key("Eve"),
-                            account_key("Ferdie"),
-                        ],
+                        well_known_accounts(),
+                        vec![],
                         account_key("Alice"),
+                        DEV_BLOCK_TIME_SECS,
+                        DEV_GENESIS_KITTIES,
                     )
                 },
                 vec![],
@@ -73,15 +87,51 @@ impl Alternative {
                 || {
                     testnet_genesis(
                         vec![authority_key("Alice"), authority_key("Bob")],
-                        vec![
-                            account_key("Alice"),
-                            account_key("Bob"),
-                            account_key("Charlie"),
-                            account_key("Dave"),
-                            account_key("Eve"),
-                            account_key("Ferdie"),
-                        ],
+                        well_known_accounts(),
+                        vec![],
+                        account_key("Alice"),
+                        LOCAL_BLOCK_TIME_SECS,
+                        0,
+                    )
+                },
+                vec![],
+                None,
+                None,
+                None,
+                None,
+            ),
+            Alternative::LocalTestnetWithAccounts(count) => ChainSpec::from_genesis(
+                "Local Testnet",
+                "local_testnet",
+                move || {
+                    let mut accounts = well_known_accounts();
+                    accounts.extend(numbered_accounts(count));
+                    testnet_genesis(
+                        vec![authority_key("Alice"), authority_key("Bob")],
+                        accounts,
+                        vec![],
+                        account_key("Alice"),
+                        LOCAL_BLOCK_TIME_SECS,
+                        0,
+                    )
+                },
+                vec![],
+                None,
+                None,
+                None,
+                None,
+            ),
+            Alternative::LocalTestnetWithEndowments(endowments) => ChainSpec::from_genesis(
+                "Local Testnet",
+                "local_testnet",
+                move || {
+                    testnet_genesis(
+                        vec![authority_key("Alice"), authority_key("Bob")],
+                        well_known_accounts(),
+                        endowments.clone(),
                         account_key("Alice"),
+                        LOCAL_BLOCK_TIME_SECS,
+                        0,
                     )
                 },
                 vec![],
@@ -110,6 +160,7 @@ impl Alternative {
                             hex!["c53308f6aa60663700587e4364da2d1e5ddcf360dfc1c9210362f506438ccb57"].unchecked_into(),
                         ],
                         hex!["e06b2b273fd42134ef5980d0feb6a0600728c54ecddb4de16114886fd41aa504"].unchecked_into(),
+                        DEMO_BLOCK_TIME_SECS,
                     )
                     },
                     vec![],
@@ -122,6 +173,7 @@ impl Alternative {
                     None,
                 )
             }
+            Alternative::DemoFromFile(path) => load_json_file(&path)?,
         })
     }
 
@@ -131,16 +183,120 @@ impl Alternative {
             "local" => Some(Alternative::LocalTestnet),
             "" | "demo" => Some(Alternative::DemoTestnet),
             "demo-latest" => Some(Alternative::DemoTestnetLatest),
+            _ if s.starts_with(LOCAL_ACCOUNTS_PREFIX) => s[LOCAL_ACCOUNTS_PREFIX.len()..]
+                .parse()
+                .ok()
+                .map(Alternative::LocalTestnetWithAccounts),
+            _ if s.starts_with(LOCAL_ENDOWMENTS_PREFIX) => {
+                parse_endowments(&s[LOCAL_ENDOWMENTS_PREFIX.len()..])
+                    .map(Alternative::LocalTestnetWithEndowments)
+            }
+            _ if s.starts_with(DEMO_FILE_PREFIX) => {
+                Some(Alternative::DemoFromFile(PathBuf::from(&s[DEMO_FILE_PREFIX.len()..])))
+            }
             _ => None,
         }
     }
 }
 
+/// Prefix of chain ids of a local testnet with extra accounts, e.g. `local-accounts:100`.
+const LOCAL_ACCOUNTS_PREFIX: &str = "local-accounts:";
+/// Prefix of chain ids of a local testnet with explicit balances, e.g. `local-endowments:Alice=1000,Bob=10`.
+const LOCAL_ENDOWMENTS_PREFIX: &str = "local-endowments:";
+/// Prefix of chain ids loading the demo testnet from a file, e.g. `demo-file:./demo.json`.
+const DEMO_FILE_PREFIX: &str = "demo-file:";
+
+/// Initial balance of each endowed account when no explicit endowments are given.
+const DEFAULT_ENDOWMENT: u128 = 1 << 60;
+
+/// Block time of the development chain. The fastest possible as `minimum_period` is in whole seconds.
+const DEV_BLOCK_TIME_SECS: u64 = 2;
+/// Block time of the local testnet.
+const LOCAL_BLOCK_TIME_SECS: u64 = 4;
+/// Block time of the demo testnet.
+const DEMO_BLOCK_TIME_SECS: u64 = 12;
+
+/// Extra kitties minted to the root key at genesis of the development chain.
+const DEV_GENESIS_KITTIES: u32 = 10;
+
+/// Timestamp `minimum_period` for a block time. Aura slots last twice the minimum period.
+fn minimum_period(block_time_secs: u64) -> u64 {
+    (block_time_secs / 2).max(1)
+}
+
+/// `DEFAULT_ENDOWMENT` for every account.
+fn default_endowments(accounts: Vec<AccountId>) -> Vec<(AccountId, u128)> {
+    accounts.into_iter().map(|k| (k, DEFAULT_ENDOWMENT)).collect()
+}
+
+/// Parse comma separated `name=balance` endowments of the accounts `//name`.
+fn parse_endowments(s: &str) -> Option<Vec<(AccountId, u128)>> {
+    s.split(',')
+        .map(|entry| {
+            let mut parts = entry.splitn(2, '=');
+            let name = parts.next()?;
+            let balance = parts.next()?.parse().ok()?;
+            let account = sr25519::Pair::from_string(&format!("//{}", name), None).ok()?.public();
+            Some((account, balance))
+        })
+        .collect()
+}
+
+/// One kitty for each of the first two endowed accounts, plus `dev_kitties` for the root key.
+fn genesis_kitties(
+    endowed_accounts: &[AccountId],
+    root_key: &AccountId,
+    dev_kitties: u32,
+) -> Vec<(AccountId, Vec<u8>)> {
+    let initial = endowed_accounts.iter().cloned().take(2);
+    let dev = (0..dev_kitties).map(|_| root_key.clone());
+    let dna_length = DnaLength::get() as usize;
+    initial
+        .chain(dev)
+        .enumerate()
+        .map(|(i, k)| (k, genesis_dna(i as u32, dna_length)))
+        .collect()
+}
+
+/// DNA of the `index`th genesis kitty, hashed from its index so every kitty is distinct.
+fn genesis_dna(index: u32, dna_length: usize) -> Vec<u8> {
+    // Further hashes are appended when the DNA is longer than a single hash
+    (0u32..)
+        .flat_map(|chunk| blake2_256(&(index, chunk).encode()).to_vec())
+        .take(dna_length)
+        .collect()
+}
+
+/// Load a chain spec from a JSON file, for ids not matching any `Alternative`.
+pub(crate) fn from_json_file(path: &str) -> Result<ChainSpec, String> {
+    load_json_file(Path::new(path))
+}
+
+/// Load a chain spec from a JSON file that must exist.
+fn load_json_file(path: &Path) -> Result<ChainSpec, String> {
+    if !path.is_file() {
+        return Err(format!("Chain spec file {} not found", path.display()));
+    }
+    ChainSpec::from_json_file(path.to_path_buf())
+        .map_err(|e| format!("Error loading chain spec {}: {}", path.display(), e))
+}
+
+/// Genesis of a testnet. Indices and balances are both built from `endowments`,
+/// which defaults to `DEFAULT_ENDOWMENT` for each of `accounts` when empty.
 fn testnet_genesis(
     initial_authorities: Vec<AuthorityId>,
-    endowed_accounts: Vec<AccountId>,
+    accounts: Vec<AccountId>,
+    endowments: Vec<(AccountId, u128)>,
     root_key: AccountId,
+    block_time_secs: u64,
+    dev_kitties: u32,
 ) -> GenesisConfig {
+    let endowments = if endowments.is_empty() {
+        default_endowments(accounts)
+    } else {
+        endowments
+    };
+    let endowed_accounts: Vec<AccountId> = endowments.iter().map(|(k, _)| k.clone()).collect();
     GenesisConfig {
 		consensus: Some(ConsensusConfig {
 			code: include_bytes!("../runtime/wasm/target/wasm32-unknown-unknown/release/substrate_kitties_runtime_wasm.compact.wasm").to_vec(),
@@ -148,7 +304,7 @@ fn testnet_genesis(
 		}),
 		system: None,
 		timestamp: Some(TimestampConfig {
-			minimum_period: 2, // 4 second block time.
+			minimum_period: minimum_period(block_time_secs),
 		}),
 		indices: Some(IndicesConfig {
 			ids: endowed_accounts.clone(),
@@ -159,9 +315,12 @@ fn testnet_genesis(
 			existential_deposit: 500,
 			transfer_fee: 0,
 			creation_fee: 0,
-			balances: endowed_accounts.iter().cloned().map(|k|(k, 1 << 60)).collect(),
+			balances: endowments,
 			vesting: vec![],
 		}),
+		kitties: Some(KittiesConfig {
+			kitties: genesis_kitties(&endowed_accounts, &root_key, dev_kitties),
+		}),
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
@@ -172,6 +331,7 @@ fn demonet_genesis(
     initial_authorities: Vec<AuthorityId>,
     endowed_accounts: Vec<AccountId>,
     root_key: AccountId,
+    block_time_secs: u64,
 ) -> GenesisConfig {
     GenesisConfig {
 		consensus: Some(ConsensusConfig {
@@ -180,7 +340,7 @@ fn demonet_genesis(
 		}),
 		system: None,
 		timestamp: Some(TimestampConfig {
-			minimum_period: 6, // 12 second block time.
+			minimum_period: minimum_period(block_time_secs),
 		}),
 		indices: Some(IndicesConfig {
 			ids: endowed_accounts.clone(),
@@ -197,5 +357,172 @@ fn demonet_genesis(
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
+		kitties: None,
 	}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use primitives::Blake2Hasher;
+    use sr_io::{with_externalities, TestExternalities};
+    use substrate_kitties_runtime::{BuildStorage, Kitties};
+
+    #[test]
+    fn testnet_genesis_uses_default_endowment() {
+        let genesis = testnet_genesis(
+            vec![authority_key("Alice")],
+            vec![account_key("Alice"), account_key("Bob")],
+            vec![],
+            account_key("Alice"),
+            DEV_BLOCK_TIME_SECS,
+            0,
+        );
+        assert_eq!(genesis.balances.unwrap().balances, vec![
+            (account_key("Alice"), DEFAULT_ENDOWMENT),
+            (account_key("Bob"), DEFAULT_ENDOWMENT),
+        ]);
+        assert_eq!(genesis.indices.unwrap().ids, vec![account_key("Alice"), account_key("Bob")]);
+        assert_eq!(genesis.kitties.unwrap().kitties.len(), 2);
+    }
+
+    #[test]
+    fn testnet_genesis_uses_explicit_endowments() {
+        let genesis = testnet_genesis(
+            vec![authority_key("Alice")],
+            well_known_accounts(),
+            vec![(account_key("Alice"), 1_000), (account_key("Bob"), 10), (account_key("Charlie"), 1)],
+            account_key("Alice"),
+            DEV_BLOCK_TIME_SECS,
+            0,
+        );
+        assert_eq!(genesis.balances.unwrap().balances, vec![
+            (account_key("Alice"), 1_000),
+            (account_key("Bob"), 10),
+            (account_key("Charlie"), 1),
+        ]);
+        // Every endowed account gets an index
+        assert_eq!(genesis.indices.unwrap().ids, vec![
+            account_key("Alice"),
+            account_key("Bob"),
+            account_key("Charlie"),
+        ]);
+    }
+
+    #[test]
+    fn testnet_genesis_mints_dev_kitties_to_root() {
+        let genesis = testnet_genesis(
+            vec![authority_key("Alice")],
+            vec![account_key("Alice"), account_key("Bob")],
+            vec![],
+            account_key("Alice"),
+            DEV_BLOCK_TIME_SECS,
+            3,
+        );
+        let kitties = genesis.kitties.unwrap().kitties;
+        let owned_by = |who: AccountId| kitties.iter().filter(|(owner, _)| *owner == who).count();
+        assert_eq!(kitties.len(), 5);
+        assert_eq!(owned_by(account_key("Alice")), 4);
+        assert_eq!(owned_by(account_key("Bob")), 1);
+    }
+
+    #[test]
+    fn testnet_genesis_builds_kitties_storage() {
+        let genesis = testnet_genesis(
+            vec![authority_key("Alice")],
+            vec![account_key("Alice"), account_key("Bob")],
+            vec![],
+            account_key("Alice"),
+            DEV_BLOCK_TIME_SECS,
+            3,
+        );
+        let mut ext: TestExternalities<Blake2Hasher> = genesis.build_storage().unwrap().0.into();
+        with_externalities(&mut ext, || {
+            assert_eq!(Kitties::kitties_count(), 5);
+            assert_eq!(Kitties::owned_kitties_count(account_key("Alice")), 4);
+            assert_eq!(Kitties::owned_kitties_count(account_key("Bob")), 1);
+            assert_eq!(Kitties::kitty_owner(1), Some(account_key("Bob")));
+            assert_eq!(Kitties::kitty(4).unwrap().dna, genesis_dna(4, DnaLength::get() as usize));
+        });
+    }
+
+    #[test]
+    fn genesis_dna_is_distinct() {
+        let dnas: Vec<_> = (0..300).map(|i| genesis_dna(i, 16)).collect();
+        for (i, dna) in dnas.iter().enumerate() {
+            assert_eq!(dna.len(), 16);
+            assert!(!dnas[..i].contains(dna));
+        }
+        assert_eq!(genesis_dna(1, 40).len(), 40);
+        assert_eq!(genesis_dna(1, 40)[..16], genesis_dna(1, 16)[..]);
+    }
+
+    #[test]
+    fn testnet_genesis_endows_numbered_accounts() {
+        let mut accounts = well_known_accounts();
+        accounts.extend(numbered_accounts(10));
+        let genesis = testnet_genesis(
+            vec![authority_key("Alice")],
+            accounts,
+            vec![],
+            account_key("Alice"),
+            LOCAL_BLOCK_TIME_SECS,
+            0,
+        );
+        let balances = genesis.balances.unwrap().balances;
+        assert_eq!(balances.len(), 16);
+        assert_eq!(balances[6], (account_key("Account0"), DEFAULT_ENDOWMENT));
+        assert_eq!(balances[15], (account_key("Account9"), DEFAULT_ENDOWMENT));
+        assert_eq!(genesis.indices.unwrap().ids.len(), 16);
+    }
+
+    #[test]
+    fn local_accounts_is_parsed() {
+        match Alternative::from("local-accounts:25") {
+            Some(Alternative::LocalTestnetWithAccounts(count)) => assert_eq!(count, 25),
+            other => panic!("Unexpected alternative {:?}", other),
+        }
+        assert!(Alternative::from("local-accounts:many").is_none());
+    }
+
+    #[test]
+    fn local_endowments_is_parsed() {
+        match Alternative::from("local-endowments:Alice=1000,Bob=10") {
+            Some(Alternative::LocalTestnetWithEndowments(endowments)) => assert_eq!(endowments, vec![
+                (account_key("Alice"), 1_000),
+                (account_key("Bob"), 10),
+            ]),
+            other => panic!("Unexpected alternative {:?}", other),
+        }
+        assert!(Alternative::from("local-endowments:").is_none());
+        assert!(Alternative::from("local-endowments:Alice=lots").is_none());
+    }
+
+    #[test]
+    fn demo_from_file_is_parsed() {
+        match Alternative::from("demo-file:/tmp/demo.json") {
+            Some(Alternative::DemoFromFile(path)) => assert_eq!(path, PathBuf::from("/tmp/demo.json")),
+            other => panic!("Unexpected alternative {:?}", other),
+        }
+    }
+
+    #[test]
+    fn demo_from_missing_file_fails_clearly() {
+        let err = Alternative::DemoFromFile(PathBuf::from("/nonexistent/demo.json")).load().err();
+        assert_eq!(err, Some("Chain spec file /nonexistent/demo.json not found".to_string()));
+    }
+
+    #[test]
+    fn unknown_chain_file_fails_clearly() {
+        let err = from_json_file("/nonexistent/chain.json").err();
+        assert_eq!(err, Some("Chain spec file /nonexistent/chain.json not found".to_string()));
+    }
+
+    #[test]
+    fn minimum_period_is_half_block_time() {
+        assert_eq!(minimum_period(DEV_BLOCK_TIME_SECS), 1);
+        assert_eq!(minimum_period(LOCAL_BLOCK_TIME_SECS), 2);
+        assert_eq!(minimum_period(DEMO_BLOCK_TIME_SECS), 6);
+        assert_eq!(minimum_period(1), 1);
+    }
+}
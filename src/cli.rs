@@ -54,7 +54,7 @@ where
 fn load_spec(id: &str) -> Result<Option<chain_spec::ChainSpec>, String> {
     Ok(match chain_spec::Alternative::from(id) {
         Some(spec) => Some(spec.load()?),
-        None => None,
+        None => Some(chain_spec::from_json_file(id)?),
     })
 }
 